@@ -0,0 +1,274 @@
+use mastermind::mastermindlib::board::{BoardSettings, GuessError, MastermindBoard, Phase};
+use mastermind::mastermindlib::codespace::CodeSpace;
+use mastermind::mastermindlib::feedback::FeedbackMode;
+use mastermind::mastermindlib::liar::Liar;
+use mastermind::mastermindlib::solver::{
+    self, BatchSolver, Codebreaker, MAX_EXHAUSTIVE_CODES, Objective, OpeningBook, OptimalSolver,
+};
+use std::time::{Duration, Instant};
+
+const USAGE: &str =
+    "Plays every strategy against every secret (or a seeded sample) and reports guess counts
+
+Usage: mastermind-bench [options]
+    --pegs <n>          code length (default 4)
+    --colors <n>        number of colors (default 6)
+    --no-repeats        codes can't repeat a color
+    --blanks            allow blank pegs
+    --positional        answer with a verdict per peg like Wordle
+    --exact-only        only report exact pegs, misplaced ones stay hidden
+    --tries <n>         guesses before a game counts as lost (default 20)
+    --static <n>        Static Mastermind, the first n guesses are answered together
+    --lies <n>          the codemaker may give up to n false answers per game
+    --adversarial       lies keep as many codes possible as they can instead of random ones
+    --sample <n>        play n random secrets instead of all of them, required above
+                        262144 codes
+    --seed <n>          seed for the sampled secrets (default 0)
+    --strategy <name>   only run this strategy, can be repeated. The optimal-expected and
+                        optimal-worst searches run by default up to 256 codes only
+    --csv               print csv instead of a table
+    --opening-book      print a freshly generated opening book instead";
+
+// The optimal searches take minutes on larger code spaces, above this they only run when
+// asked for by name
+const OPTIMAL_MAX_CODES: u128 = 256;
+
+struct Options {
+    settings: BoardSettings,
+    sample: Option<u64>,
+    seed: u64,
+    strategies: Vec<String>,
+    csv: bool,
+    opening_book: bool,
+}
+
+struct Report {
+    name: String,
+    games: usize,
+    lost: usize,
+    // Games the strategy broke off with an invalid guess, not counted as lost
+    errors: usize,
+    first_error: Option<GuessError>,
+    // Number of won games by guess count, index 0 is unused
+    histogram: Vec<usize>,
+    elapsed: Duration,
+}
+
+impl Report {
+    fn won(&self) -> usize {
+        self.games - self.lost - self.errors
+    }
+
+    fn average(&self) -> f64 {
+        let guesses: usize = self.histogram.iter().enumerate().map(|(i, n)| i * n).sum();
+        guesses as f64 / self.won().max(1) as f64
+    }
+
+    fn worst(&self) -> usize {
+        self.histogram.iter().rposition(|&n| n > 0).unwrap_or(0)
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        settings: BoardSettings::default().max_tries(20),
+        sample: None,
+        seed: 0,
+        strategies: Vec::new(),
+        csv: false,
+        opening_book: false,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--pegs" => options.settings.code_length = number(&value()?)?,
+            "--colors" => options.settings.symbols = number(&value()?)?,
+            "--tries" => options.settings.max_tries = number(&value()?)?,
+            "--no-repeats" => options.settings.allow_repeats = false,
+            "--blanks" => options.settings.allow_blanks = true,
+            "--positional" => options.settings.feedback = FeedbackMode::Positional,
+            "--exact-only" => options.settings.feedback = FeedbackMode::ExactOnly,
+            "--static" => options.settings.static_guesses = number(&value()?)?,
+            "--lies" => options.settings.lies = number(&value()?)?,
+            "--adversarial" => options.settings.liar = Liar::Adversarial,
+            "--sample" => options.sample = Some(number(&value()?)?),
+            "--seed" => options.seed = number(&value()?)?,
+            "--strategy" => options.strategies.push(value()?),
+            "--csv" => options.csv = true,
+            "--opening-book" => options.opening_book = true,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("unknown argument {arg}\n\n{USAGE}")),
+        }
+    }
+    // A board per code would not fit in memory
+    let size = options.settings.code_space_size();
+    if options.sample.is_none() && !options.opening_book && size > MAX_EXHAUSTIVE_CODES {
+        return Err(format!(
+            "{size} codes are too many to play every one, use --sample"
+        ));
+    }
+    Ok(options)
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid number {value}"))
+}
+
+fn boards(options: &Options) -> Vec<MastermindBoard> {
+    let settings = &options.settings;
+    match options.sample {
+        Some(count) => (0..count)
+            .map(|i| MastermindBoard::with_seed(settings.clone(), options.seed.wrapping_add(i)))
+            .collect(),
+        None => CodeSpace::new(settings)
+            .iter()
+            .map(|code| {
+                MastermindBoard::with_code(settings.clone(), code)
+                    .expect("code from the code space")
+            })
+            .collect(),
+    }
+}
+
+fn run(breaker: &mut dyn Codebreaker, boards: &[MastermindBoard]) -> Report {
+    let mut report = Report {
+        name: breaker.name().to_string(),
+        games: boards.len(),
+        lost: 0,
+        errors: 0,
+        first_error: None,
+        histogram: Vec::new(),
+        elapsed: Duration::ZERO,
+    };
+    let started = Instant::now();
+    for board in boards {
+        let mut board = board.clone();
+        match solver::play(breaker, &mut board) {
+            Ok(Phase::Won { tries }) => {
+                let tries = tries as usize;
+                if report.histogram.len() <= tries {
+                    report.histogram.resize(tries + 1, 0);
+                }
+                report.histogram[tries] += 1;
+            }
+            Ok(_) => report.lost += 1,
+            Err(err) => {
+                report.errors += 1;
+                report.first_error.get_or_insert(err);
+            }
+        }
+    }
+    report.elapsed = started.elapsed();
+    report
+}
+
+fn print_table(reports: &[Report]) {
+    println!(
+        "{:<20} {:>7} {:>6} {:>6} {:>8} {:>6} {:>10} {:>10}  histogram",
+        "strategy", "games", "lost", "errors", "average", "worst", "total ms", "ms/game"
+    );
+    for report in reports {
+        let histogram: Vec<String> = report
+            .histogram
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(guesses, n)| format!("{guesses}:{n}"))
+            .collect();
+        println!(
+            "{:<20} {:>7} {:>6} {:>6} {:>8.4} {:>6} {:>10.1} {:>10.3}  {}",
+            report.name,
+            report.games,
+            report.lost,
+            report.errors,
+            report.average(),
+            report.worst(),
+            report.elapsed.as_secs_f64() * 1000.0,
+            report.elapsed.as_secs_f64() * 1000.0 / report.games.max(1) as f64,
+            histogram.join(" ")
+        );
+    }
+}
+
+fn print_csv(reports: &[Report]) {
+    let columns = reports.iter().map(|r| r.histogram.len()).max().unwrap_or(0);
+    let histogram_header: Vec<String> = (1..columns).map(|i| format!("guesses_{i}")).collect();
+    println!(
+        "strategy,games,lost,errors,average,worst,total_ms,{}",
+        histogram_header.join(",")
+    );
+    for report in reports {
+        let histogram: Vec<String> = (1..columns)
+            .map(|i| report.histogram.get(i).copied().unwrap_or(0).to_string())
+            .collect();
+        println!(
+            "{},{},{},{},{:.4},{},{:.1},{}",
+            report.name,
+            report.games,
+            report.lost,
+            report.errors,
+            report.average(),
+            report.worst(),
+            report.elapsed.as_secs_f64() * 1000.0,
+            histogram.join(",")
+        );
+    }
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            std::process::exit(2);
+        }
+    };
+
+    if options.opening_book {
+        print!("{}", OpeningBook::generate().to_json());
+        return;
+    }
+
+    let mut strategies = solver::strategies();
+    if options.settings.static_guesses > 0 {
+        strategies.push(Box::new(BatchSolver::new()));
+    }
+    for objective in [Objective::Expected, Objective::WorstCase] {
+        let optimal = OptimalSolver::new(objective);
+        let named = options.strategies.iter().any(|name| name == optimal.name());
+        if named || options.settings.code_space_size() <= OPTIMAL_MAX_CODES {
+            strategies.push(Box::new(optimal));
+        }
+    }
+    if !options.strategies.is_empty() {
+        strategies.retain(|breaker| options.strategies.iter().any(|name| name == breaker.name()));
+    }
+    if strategies.is_empty() {
+        eprintln!("no strategy matches {:?}", options.strategies);
+        std::process::exit(2);
+    }
+
+    let boards = boards(&options);
+    let reports: Vec<Report> = strategies
+        .iter_mut()
+        .map(|breaker| run(breaker.as_mut(), &boards))
+        .collect();
+
+    if options.csv {
+        print_csv(&reports);
+    } else {
+        print_table(&reports);
+    }
+    for report in &reports {
+        if let Some(err) = &report.first_error {
+            eprintln!(
+                "{}: {} games ended with an invalid guess, the first one: {err}",
+                report.name, report.errors
+            );
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use mastermind::mastermindlib::board::{BoardSettings, BoardState};
+use mastermind::mastermindlib::solver::{Objective, OptimalSearch};
+use std::time::Instant;
+
+const USAGE: &str = "Searches the optimal strategy and prints it as a decision tree
+
+Usage: mastermind-tree [options]
+    --pegs <n>          code length (default 4)
+    --colors <n>        number of colors (default 6)
+    --no-repeats        codes can't repeat a color
+    --blanks            allow blank pegs
+    --worst-case        minimise the worst case instead of the average
+    --format <format>   json, dot or summary (default summary)";
+
+enum Format {
+    Json,
+    Dot,
+    Summary,
+}
+
+struct Options {
+    settings: BoardSettings,
+    objective: Objective,
+    format: Format,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        settings: BoardSettings::default(),
+        objective: Objective::Expected,
+        format: Format::Summary,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--pegs" => options.settings.code_length = number(&value()?)?,
+            "--colors" => options.settings.symbols = number(&value()?)?,
+            "--no-repeats" => options.settings.allow_repeats = false,
+            "--blanks" => options.settings.allow_blanks = true,
+            "--worst-case" => options.objective = Objective::WorstCase,
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "json" => Format::Json,
+                    "dot" => Format::Dot,
+                    "summary" => Format::Summary,
+                    other => return Err(format!("unknown format {other}")),
+                }
+            }
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("unknown argument {arg}\n\n{USAGE}")),
+        }
+    }
+    Ok(options)
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid number {value}"))
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            std::process::exit(2);
+        }
+    };
+
+    let started = Instant::now();
+    let search = OptimalSearch::new().objective(options.objective);
+    let Some(tree) = search.tree(&options.settings, &BoardState::new(&options.settings)) else {
+        eprintln!(
+            "the code space is too large for an exhaustive search (at most {} codes)",
+            search.max_codes
+        );
+        std::process::exit(1);
+    };
+
+    match options.format {
+        Format::Json => println!("{}", tree.to_json()),
+        Format::Dot => print!("{}", tree.to_dot()),
+        Format::Summary => {
+            println!(
+                "{} codes, {} guesses in total, average {:.4}, worst case {}, first guess {:?}, {:.1}s",
+                tree.candidates,
+                tree.total_guesses(),
+                tree.average(),
+                tree.worst_case(),
+                tree.guess.0,
+                started.elapsed().as_secs_f64()
+            );
+        }
+    }
+}
@@ -1,24 +1,128 @@
 pub mod mastermindlib;
 use mastermindlib::board::BoardSettings;
+use mastermindlib::board::GameState;
 use mastermindlib::board::Guess;
 use mastermindlib::board::MastermindBoard;
-
-use rgb::RGB8;
+use mastermindlib::board::Phase;
+use mastermindlib::feedback::FeedbackMode;
+use mastermindlib::solver::{self, SimpleSolver};
+use std::io::{self, BufRead, Write};
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--bulls-and-cows") {
+        play(BoardSettings::bulls_and_cows());
+        return;
+    }
+    if args.iter().any(|arg| arg == "--play") {
+        play(BoardSettings::default());
+        return;
+    }
+    if args.iter().any(|arg| arg == "--wordle") {
+        play(BoardSettings::default().feedback(FeedbackMode::Positional));
+        return;
+    }
+    if args.iter().any(|arg| arg == "--static") {
+        play(BoardSettings::default().static_guesses(6));
+        return;
+    }
+    if args.iter().any(|arg| arg == "--liar") {
+        play(BoardSettings::default().lies(1).max_tries(12));
+        return;
+    }
+    if args.iter().any(|arg| arg == "--exact-only") {
+        play(BoardSettings::default().feedback(FeedbackMode::ExactOnly));
+        return;
+    }
+
     println!("To run the gui run the project in the ui directory!");
+    println!(
+        "Play in the terminal with --play, --wordle, --exact-only, --static, --liar or --bulls-and-cows"
+    );
 
     // Debug
     let settings = BoardSettings::default()
-        .colors(vec![
-            RGB8::new(255, 0, 0),
-            RGB8::new(255, 0, 255),
-            RGB8::new(255, 255, 0),
-        ])
-        .code_length(4);
+        .symbols(3)
+        .code_length(4)
+        .max_tries(8);
     let mut board = MastermindBoard::new(settings);
-    board.guess(&Guess {
-        0: vec![1, 2, 2, 2],
-    });
+    if let Err(err) = board.try_guess(&Guess(vec![1, 2, 2, 2])) {
+        println!("{err}");
+    }
+    // Let a solver finish the game
+    match solver::play(&mut SimpleSolver, &mut board) {
+        Ok(phase) => println!("{phase:?}"),
+        Err(err) => println!("{err}"),
+    }
     println!("{:?}", board);
 }
+
+// Reads guesses from stdin until the game is over
+fn play(settings: BoardSettings) {
+    let example = settings.format_code(&(0..settings.code_length).collect::<Vec<_>>());
+    println!(
+        "Guess the code of {} symbols from 0 to {}, e.g. {example}",
+        settings.code_length,
+        settings.symbol_count() - 1
+    );
+    if settings.lies > 0 {
+        println!(
+            "Careful, some answers may be lies (at most {})",
+            settings.lies
+        );
+    }
+    let mut board = MastermindBoard::new(settings);
+    let mut lines = io::stdin().lock().lines();
+
+    while !board.is_over() {
+        print!(
+            "Guess {}/{}: ",
+            board.state.guesses.len() + board.state.pending.len() + 1,
+            board.settings.total_tries()
+        );
+        let _ = io::stdout().flush();
+        let Some(Ok(line)) = lines.next() else {
+            return;
+        };
+        let guess = match board.settings.parse_code(&line) {
+            Ok(code) => Guess(code),
+            Err(err) => {
+                println!("{err}");
+                continue;
+            }
+        };
+        let answered = board.state.answers.len();
+        match board.try_guess(&guess) {
+            Ok(GameState::BatchPending(missing)) => {
+                println!("{missing} more guesses before the batch is answered")
+            }
+            // A static batch is answered all at once
+            Ok(_) => {
+                for turn in answered..board.state.answers.len() {
+                    let answer = match board.state.verdicts.get(turn) {
+                        Some(verdicts) => {
+                            let verdicts: Vec<String> =
+                                verdicts.iter().map(|v| v.to_string()).collect();
+                            verdicts.join(", ")
+                        }
+                        None => board.settings.format_feedback(board.state.answers[turn]),
+                    };
+                    let guess = board.settings.format_code(&board.state.guesses[turn].0);
+                    println!("{guess}: {answer}");
+                }
+            }
+            Err(err) => println!("{err}"),
+        }
+    }
+
+    match board.phase() {
+        Phase::Won { tries } => println!("Solved in {tries} tries"),
+        _ => println!(
+            "Out of tries, the code was {}",
+            board.settings.format_code(&board.state.code)
+        ),
+    }
+    for turn in &board.state.lied {
+        println!("The answer to guess {} was a lie", turn + 1);
+    }
+}
@@ -1,70 +1,70 @@
-use rand::Rng;
-use rgb::RGB8;
-use std::cmp;
-use std::collections::HashMap;
-
-const COLORS: [RGB8; 9] = [
-    RGB8 {
-        r: 241,
-        g: 196,
-        b: 15,
-    },
-    RGB8 {
-        r: 26,
-        g: 188,
-        b: 156,
-    },
-    RGB8 {
-        r: 52,
-        g: 152,
-        b: 219,
-    },
-    RGB8 {
-        r: 155,
-        g: 89,
-        b: 182,
-    },
-    RGB8 {
-        r: 192,
-        g: 57,
-        b: 43,
-    },
-    RGB8 {
-        r: 243,
-        g: 156,
-        b: 18,
-    },
-    RGB8 {
-        r: 22,
-        g: 160,
-        b: 133,
-    },
-    RGB8 {
-        r: 41,
-        g: 128,
-        b: 185,
-    },
-    RGB8 {
-        r: 142,
-        g: 68,
-        b: 173,
-    },
-];
+use super::codespace::CodeSpace;
+use super::feedback::{Feedback, FeedbackMode, Verdict, score, verdicts};
+use super::hint::{Hint, HintKind, HintRecord};
+use super::liar::{self, Liar};
+use super::save::{self, SavedBoard, SavedSettings};
+use super::solver::{BatchSolver, Codebreaker, EntropySolver, MAX_EXHAUSTIVE_CODES};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt;
 
-#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize, PartialEq)]
-// Todo: Implement Default
+// Hints are answered right away, on the UI thread, so the solver gets a smaller budget
+const HINT_EVALUATIONS: usize = 2_000_000;
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, PartialEq)]
+#[serde(from = "SavedSettings")]
 pub struct BoardSettings {
-    pub colors: Vec<RGB8>,
+    // Number of colors (symbols) a code can use, how they look is up to a `Palette`
+    pub symbols: u8,
     pub code_length: u8,
     pub max_tries: u8,
-    pub is_ended: bool,
+    pub allow_repeats: bool,
+    // Adds a blank peg as an extra symbol after the colors, it is scored like any color
+    pub allow_blanks: bool,
+    pub alphabet: Alphabet,
+    pub feedback: FeedbackMode,
+    // Static Mastermind: this many guesses are submitted before any of them is answered,
+    // then a single attempt remains to name the code. Zero for the normal game
+    pub static_guesses: u8,
+    // Up to this many answers per game may be false, a winning guess is always confirmed
+    pub lies: u8,
+    pub liar: Liar,
+}
+
+// How symbols are shown to players, scoring is the same for every alphabet
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Alphabet {
+    // Colored pegs from a `Palette`, written as numbers in text
+    #[default]
+    Colors,
+    // Digits as in Bulls and Cows, codes are written without separators up to 36 symbols
+    Digits,
+}
+
+impl Default for BoardSettings {
+    // Classic Mastermind: 4 pegs, 6 colors, 10 tries
+    fn default() -> Self {
+        Self {
+            symbols: 6,
+            code_length: 4,
+            max_tries: 10,
+            allow_repeats: true,
+            allow_blanks: false,
+            alphabet: Alphabet::Colors,
+            feedback: FeedbackMode::Counts,
+            static_guesses: 0,
+            lies: 0,
+            liar: Liar::Random,
+        }
+    }
 }
 
 impl BoardSettings {
-    pub fn colors(mut self, colors: Vec<RGB8>) -> BoardSettings {
-        self.colors = colors;
+    pub fn symbols(mut self, symbols: u8) -> BoardSettings {
+        self.symbols = symbols;
         self
     }
+
     pub fn code_length(mut self, code_length: u8) -> BoardSettings {
         self.code_length = code_length;
         self
@@ -75,44 +75,419 @@ impl BoardSettings {
         self
     }
 
-    pub fn generate_colors(&mut self, number: i16) {
-        self.colors = COLORS[..number as usize].to_vec();
+    pub fn allow_repeats(mut self, allow_repeats: bool) -> BoardSettings {
+        self.allow_repeats = allow_repeats;
+        self
+    }
+
+    pub fn allow_blanks(mut self, allow_blanks: bool) -> BoardSettings {
+        self.allow_blanks = allow_blanks;
+        self
+    }
+
+    pub fn alphabet(mut self, alphabet: Alphabet) -> BoardSettings {
+        self.alphabet = alphabet;
+        self
+    }
+
+    pub fn feedback(mut self, feedback: FeedbackMode) -> BoardSettings {
+        self.feedback = feedback;
+        self
+    }
+
+    pub fn static_guesses(mut self, static_guesses: u8) -> BoardSettings {
+        self.static_guesses = static_guesses;
+        self
+    }
+
+    pub fn lies(mut self, lies: u8) -> BoardSettings {
+        self.lies = lies;
+        self
     }
 
-    pub fn with_n_colors(mut self, number: i16) -> Self {
-        self.colors = COLORS[..number as usize].to_vec();
+    pub fn liar(mut self, liar: Liar) -> BoardSettings {
+        self.liar = liar;
         self
     }
+
+    // Guesses a game allows, in Static Mastermind the batch plus the solving attempt
+    pub fn total_tries(&self) -> u8 {
+        if self.static_guesses > 0 {
+            self.static_guesses.saturating_add(1)
+        } else {
+            self.max_tries
+        }
+    }
+
+    // Bulls and Cows: 4 distinct digits, bulls are exact and cows misplaced digits
+    pub fn bulls_and_cows() -> BoardSettings {
+        BoardSettings::default()
+            .symbols(10)
+            .code_length(4)
+            .max_tries(10)
+            .allow_repeats(false)
+            .alphabet(Alphabet::Digits)
+    }
+
+    // Number of distinct symbols a code can use, including the blank
+    pub fn symbol_count(&self) -> usize {
+        usize::from(self.symbols) + usize::from(self.allow_blanks)
+    }
+
+    pub fn blank_symbol(&self) -> Option<u8> {
+        self.allow_blanks.then_some(self.symbols)
+    }
+
+    // Checks that a code fits these settings (length and symbol range), blanks may repeat
+    // even without `allow_repeats`
+    pub fn validate_code(&self, code: &[u8]) -> Result<(), GuessError> {
+        if code.len() != self.code_length as usize {
+            return Err(GuessError::WrongLength {
+                expected: self.code_length,
+                got: code.len(),
+            });
+        }
+        if let Some((position, &symbol)) = code
+            .iter()
+            .enumerate()
+            .find(|&(_, &symbol)| symbol as usize >= self.symbol_count())
+        {
+            return Err(GuessError::UnknownSymbol { position, symbol });
+        }
+        if !self.allow_repeats
+            && let Some((position, &symbol)) = code.iter().enumerate().find(|&(i, symbol)| {
+                Some(*symbol) != self.blank_symbol() && code[..i].contains(symbol)
+            })
+        {
+            return Err(GuessError::RepeatedSymbol { position, symbol });
+        }
+        Ok(())
+    }
+
+    // Code as text in the settings' alphabet, blanks are written as `_`
+    pub fn format_code(&self, code: &[u8]) -> String {
+        let symbols = code.iter().map(|&symbol| {
+            if Some(symbol) == self.blank_symbol() {
+                "_".to_string()
+            } else if self.writes_digits() {
+                char::from_digit(symbol as u32, 36)
+                    .expect("digits fit radix 36")
+                    .to_string()
+            } else {
+                symbol.to_string()
+            }
+        });
+        if self.writes_digits() {
+            symbols.collect()
+        } else {
+            symbols.collect::<Vec<_>>().join(", ")
+        }
+    }
+
+    // Reads a code written like `format_code` does and checks it against the settings
+    pub fn parse_code(&self, text: &str) -> Result<Vec<u8>, GuessError> {
+        let tokens: Vec<String> = if self.writes_digits() {
+            text.chars()
+                .filter(|c| !c.is_whitespace())
+                .map(String::from)
+                .collect()
+        } else {
+            text.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|token| !token.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let code = tokens
+            .iter()
+            .enumerate()
+            .map(|(position, token)| {
+                let symbol = match token.as_str() {
+                    "_" => self.blank_symbol(),
+                    token if self.writes_digits() => u8::from_str_radix(token, 36).ok(),
+                    token => token.parse().ok(),
+                };
+                symbol.ok_or(GuessError::InvalidText { position })
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        self.validate_code(&code)?;
+        Ok(code)
+    }
+
+    // One character per symbol, only up to radix 36. Larger digit alphabets are written
+    // with separators like colors
+    fn writes_digits(&self) -> bool {
+        self.alphabet == Alphabet::Digits && self.symbols <= 36
+    }
+
+    // Feedback in the words of the game, bulls and cows for digits
+    pub fn format_feedback(&self, feedback: Feedback) -> String {
+        let (exact, misplaced) = match self.alphabet {
+            Alphabet::Colors => ("black", "white"),
+            Alphabet::Digits => ("bull", "cow"),
+        };
+        let plural = |count: u8, word: &str| match (count, self.alphabet) {
+            (1, _) | (_, Alphabet::Colors) => format!("{count} {word}"),
+            (_, Alphabet::Digits) => format!("{count} {word}s"),
+        };
+        if self.feedback == FeedbackMode::ExactOnly {
+            return plural(feedback.exact, exact);
+        }
+        format!(
+            "{}, {}",
+            plural(feedback.exact, exact),
+            plural(feedback.misplaced, misplaced)
+        )
+    }
+
+    // Number of codes the codemaker can choose from, saturates for huge settings
+    pub fn code_space_size(&self) -> u128 {
+        CodeSpace::new(self).len()
+    }
+
+    // Whether both settings describe the same game, only the number of tries may differ
+    pub fn same_rules(&self, other: &BoardSettings) -> bool {
+        self.symbols == other.symbols
+            && self.code_length == other.code_length
+            && self.allow_repeats == other.allow_repeats
+            && self.allow_blanks == other.allow_blanks
+            && self.feedback == other.feedback
+            && self.static_guesses == other.static_guesses
+            && self.lies == other.lies
+            && self.liar == other.liar
+    }
+
+    pub fn presets() -> Vec<(&'static str, BoardSettings)> {
+        vec![
+            (
+                "Easy",
+                BoardSettings::default()
+                    .symbols(3)
+                    .code_length(4)
+                    .max_tries(5),
+            ),
+            (
+                "Classic (no repeats)",
+                BoardSettings::default()
+                    .symbols(6)
+                    .code_length(4)
+                    .max_tries(8)
+                    .allow_repeats(false),
+            ),
+            (
+                "Medium",
+                BoardSettings::default()
+                    .symbols(5)
+                    .code_length(6)
+                    .max_tries(6),
+            ),
+            (
+                "Hard",
+                BoardSettings::default()
+                    .symbols(5)
+                    .code_length(7)
+                    .max_tries(7),
+            ),
+            ("Bulls and Cows", BoardSettings::bulls_and_cows()),
+        ]
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(into = "SavedBoard", try_from = "SavedBoard")]
 pub struct MastermindBoard {
     pub settings: BoardSettings,
     pub state: BoardState,
+    pub(crate) phase: Phase,
 }
 
 impl MastermindBoard {
     pub fn new(settings: BoardSettings) -> Self {
+        Self::with_seed(settings, rand::rng().random())
+    }
+
+    pub fn with_seed(settings: BoardSettings, seed: u64) -> Self {
         Self {
-            state: BoardState::new(&settings),
+            state: BoardState::with_seed(&settings, seed),
             settings,
+            phase: Phase::InProgress,
         }
     }
 
+    // The seed is not recorded, replays of these boards fall back to the code
+    pub fn with_rng<R: Rng + ?Sized>(settings: BoardSettings, rng: &mut R) -> Self {
+        Self {
+            state: BoardState::with_rng(&settings, rng),
+            settings,
+            phase: Phase::InProgress,
+        }
+    }
+
+    // Lets a codemaker pick the secret instead of generating it
+    pub fn with_code(settings: BoardSettings, code: Vec<u8>) -> Result<Self, GuessError> {
+        Ok(Self {
+            state: BoardState::with_code(&settings, code)?,
+            settings,
+            phase: Phase::InProgress,
+        })
+    }
+
+    // Rebuilds the board from its seed (or its code) and plays the same guesses again
+    pub fn replay(&self) -> Option<Self> {
+        let mut board = match self.state.seed {
+            Some(seed) => Self::with_seed(self.settings.clone(), seed),
+            None => Self::with_code(self.settings.clone(), self.state.code.clone()).ok()?,
+        };
+        for guess in self.state.guesses.iter().chain(&self.state.pending) {
+            board.try_guess(guess).ok()?;
+        }
+        board.state.hints = self.state.hints.clone();
+        Some(board)
+    }
+
+    // Like `try_guess`, but a finished game answers with its end. A guess that doesn't fit
+    // the settings is not recorded and comes back as `GameState::Rejected`
     pub fn guess(&mut self, guess: &Guess) -> GameState {
-        if self.state.guesses.len() >= self.settings.max_tries as usize {
-            return GameState::GameEnd { 0: false };
+        match self.try_guess(guess) {
+            Ok(response) => response,
+            Err(GuessError::GameOver) => GameState::GameEnd(true),
+            Err(GuessError::TriesExhausted) => GameState::GameEnd(false),
+            Err(err) => GameState::Rejected(err),
         }
+    }
 
-        let response = self.state.guess(guess);
+    pub fn try_guess(&mut self, guess: &Guess) -> Result<GameState, GuessError> {
+        match self.phase {
+            Phase::Won { .. } => return Err(GuessError::GameOver),
+            Phase::Lost => return Err(GuessError::TriesExhausted),
+            Phase::InProgress => {}
+        }
+        self.settings.validate_code(&guess.0)?;
 
-        // Check if that was the last try
-        if let GameState::GuessAnswer(..) = response {
-            if self.state.guesses.len() >= self.settings.max_tries as usize {
-                return GameState::GameEnd { 0: false };
+        let response = if self.is_collecting_batch() {
+            self.state.pending.push(guess.clone());
+            let missing = self.settings.static_guesses as usize - self.state.pending.len();
+            if missing > 0 {
+                return Ok(GameState::BatchPending(missing as u8));
+            }
+            // The batch is complete, answer all of it at once
+            let batch = std::mem::take(&mut self.state.pending);
+            batch
+                .iter()
+                .map(|guess| self.state.guess(&self.settings, guess))
+                .last()
+                .expect("a batch has at least one guess")
+        } else {
+            self.state.guess(&self.settings, guess)
+        };
+        self.phase = Phase::from_history(&self.settings, &self.state);
+
+        // A static batch can contain the code anywhere, and this may have been the last try
+        match self.phase {
+            Phase::Won { .. } => Ok(GameState::GameEnd(true)),
+            Phase::Lost => Ok(GameState::GameEnd(false)),
+            Phase::InProgress => Ok(response),
+        }
+    }
+
+    // Answers the hint and records it in the history, hints don't use up tries
+    pub fn hint(&mut self, kind: HintKind) -> Result<Hint, GuessError> {
+        match self.phase {
+            Phase::Won { .. } => return Err(GuessError::GameOver),
+            Phase::Lost => return Err(GuessError::TriesExhausted),
+            Phase::InProgress => {}
+        }
+        if !self.hint_available(kind) {
+            return Err(GuessError::HintUnavailable);
+        }
+        let hint = match kind {
+            // Until a static batch is complete none of its guesses tells anything yet
+            HintKind::SuggestGuess if self.is_collecting_batch() => {
+                Hint::Suggestion(BatchSolver::new().next_guess(&self.settings, &self.state))
+            }
+            // Code spaces too large to enumerate get a sampled suggestion
+            HintKind::SuggestGuess => Hint::Suggestion(
+                EntropySolver::new()
+                    .max_evaluations(HINT_EVALUATIONS)
+                    .next_guess(&self.settings, &self.state),
+            ),
+            HintKind::RevealPeg => (0..self.state.code.len())
+                .map(|position| Hint::Peg {
+                    position,
+                    symbol: self.state.code[position],
+                })
+                .find(|peg| !self.hints_given().any(|hint| hint == peg))
+                .ok_or(GuessError::NoHintLeft)?,
+            HintKind::EliminateColor => {
+                // The blank isn't a color
+                let symbol = (0..self.settings.symbols)
+                    .find(|symbol| {
+                        !self.state.code.contains(symbol)
+                            && !self
+                                .hints_given()
+                                .any(|hint| *hint == Hint::Absent(*symbol))
+                    })
+                    .ok_or(GuessError::NoHintLeft)?;
+                Hint::Absent(symbol)
             }
+            HintKind::CountRemaining => {
+                Hint::Remaining(self.state.count_consistent(&self.settings))
+            }
+        };
+        self.state.hints.push(HintRecord {
+            turn: self.state.guesses.len(),
+            hint: hint.clone(),
+        });
+        Ok(hint)
+    }
+
+    // Whether the hint can be answered in reasonable time, counting walks the whole code space
+    pub fn hint_available(&self, kind: HintKind) -> bool {
+        match kind {
+            _ if self.is_over() => false,
+            HintKind::CountRemaining => self.settings.code_space_size() <= MAX_EXHAUSTIVE_CODES,
+            HintKind::SuggestGuess | HintKind::RevealPeg | HintKind::EliminateColor => true,
+        }
+    }
+
+    fn hints_given(&self) -> impl Iterator<Item = &Hint> {
+        self.state.hints.iter().map(|record| &record.hint)
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    // Whether guesses are still being collected for a static batch
+    pub fn is_collecting_batch(&self) -> bool {
+        self.settings.static_guesses > 0 && self.state.guesses.is_empty()
+    }
+
+    // Result of a finished game, None while it is still running
+    pub fn outcome(&self) -> Option<Outcome> {
+        let won = match self.phase {
+            Phase::InProgress => return None,
+            Phase::Won { .. } => true,
+            Phase::Lost => false,
+        };
+        Some(Outcome {
+            won,
+            tries: self.state.guesses.len() as u8,
+            hints: self.state.hints.len(),
+        })
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.phase != Phase::InProgress
+    }
+
+    pub fn remaining_tries(&self) -> u8 {
+        match self.phase {
+            Phase::InProgress => self
+                .settings
+                .total_tries()
+                .saturating_sub((self.state.guesses.len() + self.state.pending.len()) as u8),
+            _ => 0,
         }
-        response
     }
 }
 
@@ -122,75 +497,635 @@ impl Default for MastermindBoard {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct BoardState {
     pub code: Vec<u8>,
+    pub seed: Option<u64>,
     pub guesses: Vec<Guess>,
-    pub answers: Vec<GameState>,
+    #[serde(deserialize_with = "save::deserialize_answers")]
+    pub answers: Vec<Feedback>,
+    // Added in save version 5
+    #[serde(default)]
+    pub hints: Vec<HintRecord>,
+    // One entry per guess with `FeedbackMode::Positional`, empty otherwise. Added in
+    // save version 7
+    #[serde(default)]
+    pub verdicts: Vec<Vec<Verdict>>,
+    // Submitted guesses of a static batch that aren't answered yet. Added in save version 8
+    #[serde(default)]
+    pub pending: Vec<Guess>,
+    // Turns whose answer was a lie, as secret as the code. Added in save version 9
+    #[serde(default)]
+    pub lied: Vec<usize>,
 }
 
 impl BoardState {
     pub fn new(settings: &BoardSettings) -> Self {
-        let mut rng = rand::rng();
+        Self::with_seed(settings, rand::rng().random())
+    }
 
-        // Generate a random solution
-        let solution: Vec<u8> = (0..settings.code_length)
-            .map(|_| rng.random_range(0..settings.colors.len() as u8))
-            .collect();
+    pub fn with_seed(settings: &BoardSettings, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..Self::with_rng(settings, &mut StdRng::seed_from_u64(seed))
+        }
+    }
+
+    pub fn with_rng<R: Rng + ?Sized>(settings: &BoardSettings, rng: &mut R) -> Self {
+        let solution = CodeSpace::new(settings).random_code(rng);
 
         Self {
             guesses: Vec::new(),
             answers: Vec::new(),
+            hints: Vec::new(),
+            verdicts: Vec::new(),
+            pending: Vec::new(),
+            lied: Vec::new(),
             code: solution,
+            seed: None,
         }
     }
 
-    fn guess(&mut self, guess: &Guess) -> GameState {
-        self.guesses.push(guess.clone());
-        // Convert the code numbers to a hashmap
-        let numbers_in_code: HashMap<_, _> = self
-            .code
-            .iter()
-            .map(|&n| (n, self.code.iter().filter(|&&x| x == n).count()))
-            .collect();
+    pub fn with_code(settings: &BoardSettings, code: Vec<u8>) -> Result<Self, GuessError> {
+        settings.validate_code(&code)?;
+        Ok(Self {
+            guesses: Vec::new(),
+            answers: Vec::new(),
+            hints: Vec::new(),
+            verdicts: Vec::new(),
+            pending: Vec::new(),
+            lied: Vec::new(),
+            code,
+            seed: None,
+        })
+    }
 
-        // Compare the number count for each number
-        let right_numbers_count: u8 = numbers_in_code
+    // Any answer counts, in Static Mastermind the code may be in the middle of the batch
+    pub fn is_won(&self) -> bool {
+        self.answers
             .iter()
-            .map(|(&k, &v)| cmp::min(v, guess.0.iter().filter(|&&x| x == k).count()) as u8)
-            .sum();
+            .any(|answer| usize::from(answer.exact) == self.code.len())
+    }
+
+    // Whether `code` could be the secret given every answer so far, with up to
+    // `settings.lies` of them false
+    pub fn is_consistent(&self, settings: &BoardSettings, code: &[u8]) -> bool {
+        // Wins are never lied about, so a guess that didn't win isn't the code
+        if self.guesses.iter().any(|guess| guess.0 == code) {
+            return false;
+        }
+        let mut mismatches =
+            self.guesses
+                .iter()
+                .enumerate()
+                .filter(|&(turn, guess)| match settings.feedback {
+                    FeedbackMode::Counts => score(code, &guess.0) != self.answers[turn],
+                    FeedbackMode::Positional => verdicts(code, &guess.0) != self.verdicts[turn],
+                    FeedbackMode::ExactOnly => {
+                        score(code, &guess.0).exact != self.answers[turn].exact
+                    }
+                });
+        // Stops at the first mismatch too many
+        mismatches.nth(settings.lies as usize).is_none()
+    }
 
-        // Check each index
-        let right_position_count: u8 = guess
-            .0
+    pub fn consistent_candidates<'a>(
+        &'a self,
+        settings: &'a BoardSettings,
+    ) -> impl Iterator<Item = Guess> + 'a {
+        CodeSpace::new(settings)
             .iter()
-            .zip(self.code.iter())
-            .filter(|(x, y)| x == y)
-            .count() as u8;
+            .filter(move |code| self.is_consistent(settings, code))
+            .map(Guess)
+    }
 
-        let answer = GameState::GuessAnswer {
-            0: right_position_count,
-            1: right_numbers_count - right_position_count,
-        };
-        self.answers.push(answer.clone());
+    pub fn count_consistent(&self, settings: &BoardSettings) -> u64 {
+        self.consistent_candidates(settings).count() as u64
+    }
 
-        if usize::from(right_position_count) == self.code.len() {
-            return GameState::GameEnd { 0: true };
+    fn guess(&mut self, settings: &BoardSettings, guess: &Guess) -> GameState {
+        // A lie answers for a different code
+        let answered = match liar::stand_in(settings, self, &guess.0) {
+            Some(code) => {
+                self.lied.push(self.guesses.len());
+                code
+            }
+            None => self.code.clone(),
+        };
+        self.guesses.push(guess.clone());
+        let mut answer = score(&answered, &guess.0);
+        // Not even recorded, so nothing can peek at it
+        if settings.feedback == FeedbackMode::ExactOnly {
+            answer.misplaced = 0;
         }
+        self.answers.push(answer);
+        // Recorded for winning guesses too, a static batch goes on after one
+        let verdicts = (settings.feedback == FeedbackMode::Positional).then(|| {
+            let verdicts = verdicts(&answered, &guess.0);
+            self.verdicts.push(verdicts.clone());
+            verdicts
+        });
 
-        answer
+        if usize::from(answer.exact) == self.code.len() {
+            return GameState::GameEnd(true);
+        }
+        match verdicts {
+            Some(verdicts) => GameState::GuessVerdicts(verdicts),
+            None => GameState::GuessAnswer(answer),
+        }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct Guess(pub Vec<u8>);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub enum GameState {
-    GuessAnswer(u8, u8),
-    // (#right_pos, #right_not_pos)
-    GameEnd(bool),
+    GuessAnswer(Feedback),
+    // Answer with `FeedbackMode::Positional`, one verdict per peg
+    GuessVerdicts(Vec<Verdict>),
+    // The guess joined a static batch, this many are missing before it is answered
+    BatchPending(u8),
     // (has_won)
+    GameEnd(bool),
+    // The guess doesn't fit the settings and was not played, only from `guess`
+    Rejected(GuessError),
 }
 
 impl GameState {}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Phase {
+    #[default]
+    InProgress,
+    Won {
+        tries: u8,
+    },
+    Lost,
+}
+
+impl Phase {
+    // Derives the phase from the recorded guesses, after every guess and for older saves
+    pub fn from_history(settings: &BoardSettings, state: &BoardState) -> Self {
+        if state.is_won() {
+            Phase::Won {
+                tries: state.guesses.len() as u8,
+            }
+        } else if state.guesses.len() >= settings.total_tries() as usize {
+            Phase::Lost
+        } else {
+            Phase::InProgress
+        }
+    }
+}
+
+// How a finished game went, for scoring and statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub won: bool,
+    pub tries: u8,
+    pub hints: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum GuessError {
+    WrongLength { expected: u8, got: usize },
+    UnknownSymbol { position: usize, symbol: u8 },
+    RepeatedSymbol { position: usize, symbol: u8 },
+    GameOver,
+    TriesExhausted,
+    // Every peg or every absent symbol was already revealed
+    NoHintLeft,
+    // The hint would take too long on settings this large
+    HintUnavailable,
+    // Text that doesn't name a symbol, `position` counts symbols not characters
+    InvalidText { position: usize },
+}
+
+impl fmt::Display for GuessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GuessError::WrongLength { expected, got } => {
+                write!(f, "expected {expected} pegs but got {got}")
+            }
+            GuessError::UnknownSymbol { position, symbol } => {
+                write!(f, "unknown symbol {symbol} at position {position}")
+            }
+            GuessError::RepeatedSymbol { position, symbol } => {
+                write!(f, "symbol {symbol} at position {position} is repeated")
+            }
+            GuessError::GameOver => write!(f, "the game is already over"),
+            GuessError::TriesExhausted => write!(f, "no tries left"),
+            GuessError::NoHintLeft => write!(f, "this hint has nothing left to reveal"),
+            GuessError::HintUnavailable => {
+                write!(f, "this hint is not available for these settings")
+            }
+            GuessError::InvalidText { position } => {
+                write!(f, "can't read the symbol at position {position}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GuessError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guess(code: &[u8]) -> Guess {
+        Guess(code.to_vec())
+    }
+
+    #[test]
+    fn exact_only_answers_ignore_misplaced_pegs() {
+        let settings = BoardSettings::default().feedback(FeedbackMode::ExactOnly);
+        let mut board = MastermindBoard::with_code(settings.clone(), vec![0, 1, 2, 3]).unwrap();
+        let answer = board.try_guess(&guess(&[1, 0, 2, 3])).unwrap();
+        let GameState::GuessAnswer(feedback) = answer else {
+            panic!("expected an answer, got {answer:?}");
+        };
+        assert_eq!(
+            feedback,
+            Feedback {
+                exact: 2,
+                misplaced: 0
+            }
+        );
+        assert_eq!(settings.format_feedback(feedback), "2 black");
+
+        // Every code with two pegs right fits, whatever the others are
+        let expected = CodeSpace::new(&settings)
+            .iter()
+            .filter(|code| *code != [1, 0, 2, 3] && score(code, &[1, 0, 2, 3]).exact == 2)
+            .count();
+        assert_eq!(board.state.count_consistent(&settings), expected as u64);
+        assert!(board.state.is_consistent(&settings, &[5, 5, 2, 3]));
+        assert!(!board.state.is_consistent(&settings, &[1, 0, 2, 4]));
+    }
+
+    #[test]
+    fn batch_won_in_the_middle_keeps_a_verdict_per_guess() {
+        let settings = BoardSettings::default()
+            .symbols(4)
+            .code_length(3)
+            .feedback(FeedbackMode::Positional)
+            .static_guesses(3);
+        let mut board = MastermindBoard::with_code(settings, vec![1, 2, 3]).unwrap();
+        board.try_guess(&guess(&[0, 0, 0])).unwrap();
+        board.try_guess(&guess(&[1, 2, 3])).unwrap();
+        let state = board.try_guess(&guess(&[3, 2, 1])).unwrap();
+
+        assert!(matches!(state, GameState::GameEnd(true)));
+        assert_eq!(board.state.verdicts.len(), 3);
+        assert_eq!(board.state.verdicts[1], vec![Verdict::Correct; 3]);
+        assert_eq!(
+            board.state.verdicts[2],
+            vec![Verdict::Present, Verdict::Correct, Verdict::Present]
+        );
+    }
+
+    #[test]
+    fn suggestions_during_a_batch_are_not_repeated() {
+        let settings = BoardSettings::default()
+            .symbols(4)
+            .code_length(3)
+            .static_guesses(4);
+        let mut board = MastermindBoard::with_code(settings, vec![3, 1, 0]).unwrap();
+        while board.is_collecting_batch() {
+            let Ok(Hint::Suggestion(suggestion)) = board.hint(HintKind::SuggestGuess) else {
+                panic!("expected a suggestion");
+            };
+            assert!(!board.state.pending.contains(&suggestion));
+            board.try_guess(&suggestion).unwrap();
+        }
+    }
+
+    #[test]
+    fn suggestions_skip_batch_guesses_entered_out_of_order() {
+        let settings = BoardSettings::default()
+            .symbols(4)
+            .code_length(3)
+            .static_guesses(4);
+        let batch = BatchSolver::new()
+            .identifying_batch(&settings)
+            .unwrap()
+            .guesses;
+        let mut board = MastermindBoard::with_code(settings, vec![3, 1, 0]).unwrap();
+        // The second guess of the batch first, then one of the player's own
+        board.try_guess(&batch[1]).unwrap();
+        board.try_guess(&guess(&[0, 2, 2])).unwrap();
+        while board.is_collecting_batch() {
+            let Ok(Hint::Suggestion(suggestion)) = board.hint(HintKind::SuggestGuess) else {
+                panic!("expected a suggestion");
+            };
+            assert!(!board.state.pending.contains(&suggestion), "{suggestion:?}");
+            board.try_guess(&suggestion).unwrap();
+        }
+        assert!(board.state.guesses.contains(&batch[0]));
+    }
+
+    #[test]
+    fn guess_rejects_invalid_input_without_playing_it() {
+        let mut board =
+            MastermindBoard::with_code(BoardSettings::default(), vec![0, 1, 2, 3]).unwrap();
+        assert!(matches!(
+            board.guess(&guess(&[0, 1])),
+            GameState::Rejected(GuessError::WrongLength {
+                expected: 4,
+                got: 2
+            })
+        ));
+        assert!(matches!(
+            board.guess(&guess(&[0, 1, 2, 9])),
+            GameState::Rejected(GuessError::UnknownSymbol {
+                position: 3,
+                symbol: 9
+            })
+        ));
+        assert!(board.state.guesses.is_empty());
+        assert!(matches!(
+            board.guess(&guess(&[0, 1, 2, 3])),
+            GameState::GameEnd(true)
+        ));
+        assert!(matches!(
+            board.guess(&guess(&[0, 1, 2, 3])),
+            GameState::GameEnd(true)
+        ));
+    }
+
+    #[test]
+    fn seeded_boards_replay_the_same_game() {
+        let settings = BoardSettings::default();
+        let mut board = MastermindBoard::with_seed(settings.clone(), 42);
+        let again = MastermindBoard::with_seed(settings.clone(), 42);
+        assert_eq!(board.state.code, again.state.code);
+        // The same generator drawn through `with_rng` makes the same code, without a seed
+        let drawn = MastermindBoard::with_rng(settings, &mut StdRng::seed_from_u64(42));
+        assert_eq!(drawn.state.code, board.state.code);
+        assert_eq!(drawn.state.seed, None);
+
+        board.try_guess(&guess(&[0, 0, 1, 1])).unwrap();
+        board.try_guess(&guess(&[2, 3, 4, 5])).unwrap();
+        let replayed = board.replay().unwrap();
+        assert_eq!(replayed.state.code, board.state.code);
+        assert_eq!(replayed.state.guesses, board.state.guesses);
+        assert_eq!(replayed.state.answers, board.state.answers);
+    }
+
+    #[test]
+    fn with_code_rejects_codes_that_dont_fit() {
+        let settings = BoardSettings::default();
+        assert!(matches!(
+            MastermindBoard::with_code(settings.clone(), vec![0, 1, 2]),
+            Err(GuessError::WrongLength {
+                expected: 4,
+                got: 3
+            })
+        ));
+        assert!(matches!(
+            MastermindBoard::with_code(settings.clone(), vec![0, 1, 6, 2]),
+            Err(GuessError::UnknownSymbol {
+                position: 2,
+                symbol: 6
+            })
+        ));
+
+        // Without a seed a replay starts from the code
+        let mut board = MastermindBoard::with_code(settings, vec![5, 4, 3, 2]).unwrap();
+        assert_eq!(board.state.seed, None);
+        board.try_guess(&guess(&[2, 3, 4, 5])).unwrap();
+        let replayed = board.replay().unwrap();
+        assert_eq!(replayed.state.code, [5, 4, 3, 2]);
+        assert_eq!(replayed.state.answers, board.state.answers);
+    }
+
+    #[test]
+    fn phase_ends_on_a_win_or_the_last_try() {
+        let settings = BoardSettings::default().max_tries(3);
+        let mut board = MastermindBoard::with_code(settings.clone(), vec![0, 1, 2, 3]).unwrap();
+        assert_eq!(board.phase(), Phase::InProgress);
+        assert_eq!(board.remaining_tries(), 3);
+        board.try_guess(&guess(&[0, 0, 0, 0])).unwrap();
+        assert_eq!(board.remaining_tries(), 2);
+        assert!(matches!(
+            board.try_guess(&guess(&[0, 1, 2, 3])),
+            Ok(GameState::GameEnd(true))
+        ));
+        assert_eq!(board.phase(), Phase::Won { tries: 2 });
+        assert_eq!(board.remaining_tries(), 0);
+        assert!(board.is_over());
+        assert_eq!(
+            board.try_guess(&guess(&[0, 1, 2, 3])).unwrap_err(),
+            GuessError::GameOver
+        );
+
+        let mut board = MastermindBoard::with_code(settings, vec![0, 1, 2, 3]).unwrap();
+        for _ in 0..2 {
+            board.try_guess(&guess(&[5, 5, 5, 5])).unwrap();
+        }
+        assert!(matches!(
+            board.try_guess(&guess(&[5, 5, 5, 5])),
+            Ok(GameState::GameEnd(false))
+        ));
+        assert_eq!(board.phase(), Phase::Lost);
+        assert_eq!(board.remaining_tries(), 0);
+        assert_eq!(
+            board.try_guess(&guess(&[0, 1, 2, 3])).unwrap_err(),
+            GuessError::TriesExhausted
+        );
+        assert_eq!(board.state.guesses.len(), 3);
+    }
+
+    #[test]
+    fn codes_without_repeats_use_every_color_once() {
+        let settings = BoardSettings::default()
+            .symbols(6)
+            .code_length(4)
+            .allow_repeats(false);
+        assert_eq!(settings.code_space_size(), 360);
+        for seed in 0..200 {
+            let code = MastermindBoard::with_seed(settings.clone(), seed)
+                .state
+                .code;
+            assert_eq!(settings.validate_code(&code), Ok(()));
+            assert!(
+                code.iter()
+                    .all(|symbol| code.iter().filter(|&s| s == symbol).count() == 1)
+            );
+        }
+        assert_eq!(
+            settings.validate_code(&[0, 1, 2, 1]),
+            Err(GuessError::RepeatedSymbol {
+                position: 3,
+                symbol: 1
+            })
+        );
+        let mut board = MastermindBoard::with_code(settings, vec![0, 1, 2, 3]).unwrap();
+        assert_eq!(
+            board.try_guess(&guess(&[4, 4, 5, 5])).unwrap_err(),
+            GuessError::RepeatedSymbol {
+                position: 1,
+                symbol: 4
+            }
+        );
+        assert!(board.state.guesses.is_empty());
+    }
+
+    #[test]
+    fn blanks_repeat_without_repeats() {
+        let settings = BoardSettings::default()
+            .symbols(2)
+            .code_length(4)
+            .allow_repeats(false)
+            .allow_blanks(true);
+        // 1 code of blanks only, 8 with one color and 12 with both
+        assert_eq!(settings.code_space_size(), 21);
+        assert_eq!(settings.validate_code(&[2, 0, 2, 2]), Ok(()));
+        assert_eq!(
+            settings.validate_code(&[1, 2, 1, 2]),
+            Err(GuessError::RepeatedSymbol {
+                position: 2,
+                symbol: 1
+            })
+        );
+        for seed in 0..200 {
+            let code = MastermindBoard::with_seed(settings.clone(), seed)
+                .state
+                .code;
+            assert_eq!(settings.validate_code(&code), Ok(()));
+        }
+        let board = MastermindBoard::new(settings.clone());
+        assert_eq!(board.state.count_consistent(&settings), 21);
+    }
+
+    #[test]
+    fn blanks_are_never_eliminated() {
+        let settings = BoardSettings::default()
+            .symbols(4)
+            .code_length(3)
+            .allow_repeats(false)
+            .allow_blanks(true);
+        let mut board = MastermindBoard::with_code(settings, vec![4, 4, 4]).unwrap();
+        for _ in 0..4 {
+            assert!(matches!(
+                board.hint(HintKind::EliminateColor),
+                Ok(Hint::Absent(symbol)) if symbol < 4
+            ));
+        }
+        assert_eq!(
+            board.hint(HintKind::EliminateColor),
+            Err(GuessError::NoHintLeft)
+        );
+    }
+
+    #[test]
+    fn hints_run_out_and_count_towards_the_outcome() {
+        let settings = BoardSettings::default().symbols(6).code_length(4);
+        let mut board = MastermindBoard::with_code(settings, vec![0, 1, 1, 2]).unwrap();
+        assert_eq!(
+            board.hint(HintKind::CountRemaining),
+            Ok(Hint::Remaining(1296))
+        );
+        for position in 0..4 {
+            assert!(matches!(
+                board.hint(HintKind::RevealPeg),
+                Ok(Hint::Peg { position: revealed, .. }) if revealed == position
+            ));
+        }
+        assert_eq!(board.hint(HintKind::RevealPeg), Err(GuessError::NoHintLeft));
+        assert_eq!(board.hint(HintKind::EliminateColor), Ok(Hint::Absent(3)));
+        assert_eq!(board.hint(HintKind::EliminateColor), Ok(Hint::Absent(4)));
+        assert_eq!(board.hint(HintKind::EliminateColor), Ok(Hint::Absent(5)));
+        assert_eq!(
+            board.hint(HintKind::EliminateColor),
+            Err(GuessError::NoHintLeft)
+        );
+
+        board.try_guess(&guess(&[0, 0, 1, 1])).unwrap();
+        let remaining = board.state.count_consistent(&board.settings);
+        assert_eq!(
+            board.hint(HintKind::CountRemaining),
+            Ok(Hint::Remaining(remaining))
+        );
+        assert_eq!(board.state.hints.last().unwrap().turn, 1);
+        board.try_guess(&guess(&[0, 1, 1, 2])).unwrap();
+        assert_eq!(board.hint(HintKind::RevealPeg), Err(GuessError::GameOver));
+        assert_eq!(
+            board.outcome(),
+            Some(Outcome {
+                won: true,
+                tries: 2,
+                hints: 9
+            })
+        );
+    }
+
+    #[test]
+    fn hint_messages_use_the_alphabet_and_count_positions_from_one() {
+        let settings = BoardSettings::bulls_and_cows()
+            .symbols(12)
+            .allow_blanks(true);
+        assert_eq!(
+            Hint::Suggestion(guess(&[10, 1, 12, 3])).message(&settings),
+            "try a1_3"
+        );
+        assert_eq!(
+            Hint::Peg {
+                position: 0,
+                symbol: 11
+            }
+            .message(&settings),
+            "symbol b is at position 1"
+        );
+        assert_eq!(
+            Hint::Absent(4).message(&BoardSettings::default()),
+            "symbol 4 is not in the code"
+        );
+        assert_eq!(
+            Hint::Remaining(1).message(&settings),
+            "only one code is still possible"
+        );
+    }
+
+    #[test]
+    fn bulls_and_cows_reads_and_writes_digits() {
+        let settings = BoardSettings::bulls_and_cows();
+        assert_eq!(settings.format_code(&[1, 0, 9, 4]), "1094");
+        assert_eq!(settings.parse_code(" 10 94"), Ok(vec![1, 0, 9, 4]));
+        assert_eq!(
+            settings.parse_code("10!4"),
+            Err(GuessError::InvalidText { position: 2 })
+        );
+        assert_eq!(
+            settings.parse_code("1091"),
+            Err(GuessError::RepeatedSymbol {
+                position: 3,
+                symbol: 1
+            })
+        );
+        let feedback = Feedback {
+            exact: 1,
+            misplaced: 2,
+        };
+        assert_eq!(settings.format_feedback(feedback), "1 bull, 2 cows");
+
+        let colors = BoardSettings::default().allow_blanks(true);
+        assert_eq!(colors.format_code(&[0, 6, 5, 1]), "0, _, 5, 1");
+        assert_eq!(colors.parse_code("0 _,5, 1"), Ok(vec![0, 6, 5, 1]));
+        assert_eq!(colors.format_feedback(feedback), "1 black, 2 white");
+    }
+
+    #[test]
+    fn digit_codes_round_trip_past_radix_36() {
+        let settings = BoardSettings::default()
+            .symbols(40)
+            .code_length(2)
+            .alphabet(Alphabet::Digits);
+        assert_eq!(settings.format_code(&[37, 1]), "37, 1");
+        assert_eq!(settings.parse_code("37, 1"), Ok(vec![37, 1]));
+        let settings = settings.symbols(36);
+        assert_eq!(settings.format_code(&[35, 1]), "z1");
+        assert_eq!(settings.parse_code("z1"), Ok(vec![35, 1]));
+    }
+}
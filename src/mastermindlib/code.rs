@@ -0,0 +1,185 @@
+use super::board::{BoardSettings, Guess};
+use super::feedback::{Feedback, Verdict};
+
+pub const MAX_PEGS: usize = 16;
+pub const MAX_SYMBOLS: usize = 16;
+
+// A code packed into a u64 with 4 bits per peg, the first peg in the highest used nibble
+// so that codes of the same length compare like their symbol slices. Scoring two codes
+// touches no heap at all, which is what the exhaustive solvers spend their time on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Code {
+    packed: u64,
+    len: u8,
+}
+
+impl Code {
+    // None if the code is longer than 16 pegs or uses a symbol above 15
+    pub fn new(symbols: &[u8]) -> Option<Self> {
+        if symbols.len() > MAX_PEGS || symbols.iter().any(|&s| s as usize >= MAX_SYMBOLS) {
+            return None;
+        }
+        let packed = symbols
+            .iter()
+            .fold(0u64, |packed, &symbol| packed << 4 | symbol as u64);
+        Some(Self {
+            packed,
+            len: symbols.len() as u8,
+        })
+    }
+
+    // Whether every code of these settings fits into a `Code` and its space into a u64 rank
+    pub fn supports(settings: &BoardSettings) -> bool {
+        settings.symbol_count() <= MAX_SYMBOLS
+            && settings.code_length as usize <= MAX_PEGS
+            && settings.code_space_size() <= u64::MAX as u128
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, position: usize) -> u8 {
+        debug_assert!(position < self.len());
+        (self.packed >> (4 * (self.len() - 1 - position)) & 0xf) as u8
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len()).map(|position| self.get(position))
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.symbols().collect()
+    }
+
+    // Same result as `feedback::score` on the unpacked codes
+    pub fn score(&self, guess: &Code) -> Feedback {
+        debug_assert_eq!(self.len, guess.len);
+        // Positive counts are unmatched code symbols, negative ones unmatched guess symbols
+        let mut balance = [0i8; MAX_SYMBOLS];
+        let (mut code, mut guess) = (self.packed, guess.packed);
+        let mut exact = 0;
+        let mut misplaced = 0;
+        for _ in 0..self.len {
+            let (c, g) = ((code & 0xf) as usize, (guess & 0xf) as usize);
+            code >>= 4;
+            guess >>= 4;
+            if c == g {
+                exact += 1;
+                continue;
+            }
+            misplaced += u8::from(balance[c] < 0);
+            balance[c] += 1;
+            misplaced += u8::from(balance[g] > 0);
+            balance[g] -= 1;
+        }
+        Feedback { exact, misplaced }
+    }
+
+    // Same as `score(guess).exact`, a nibble is zero in the xor where the pegs match
+    pub fn exact_matches(&self, guess: &Code) -> u8 {
+        debug_assert_eq!(self.len, guess.len);
+        let mut diff = self.packed ^ guess.packed;
+        diff |= diff >> 1;
+        diff |= diff >> 2;
+        let used = if self.len() == MAX_PEGS {
+            u64::MAX
+        } else {
+            (1u64 << (4 * self.len())) - 1
+        };
+        let differing = (diff & 0x1111_1111_1111_1111 & used).count_ones();
+        self.len - differing as u8
+    }
+
+    // `feedback::verdicts` as a base 3 number in `Verdict` order, first peg most significant
+    pub fn verdict_index(&self, guess: &Code) -> usize {
+        debug_assert_eq!(self.len, guess.len);
+        let mut unmatched = [0u8; MAX_SYMBOLS];
+        for position in 0..self.len() {
+            let c = self.get(position);
+            if c != guess.get(position) {
+                unmatched[c as usize] += 1;
+            }
+        }
+        (0..self.len()).fold(0, |index, position| {
+            let g = guess.get(position);
+            let verdict = if self.get(position) == g {
+                Verdict::Correct
+            } else if unmatched[g as usize] > 0 {
+                unmatched[g as usize] -= 1;
+                Verdict::Present
+            } else {
+                Verdict::Absent
+            };
+            index * 3 + verdict as usize
+        })
+    }
+}
+
+impl TryFrom<&Guess> for Code {
+    type Error = ();
+
+    fn try_from(guess: &Guess) -> Result<Self, Self::Error> {
+        Code::new(&guess.0).ok_or(())
+    }
+}
+
+impl From<Code> for Guess {
+    fn from(code: Code) -> Guess {
+        Guess(code.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mastermindlib::codespace::CodeSpace;
+    use crate::mastermindlib::feedback::{score, verdicts};
+
+    fn code(symbols: &[u8]) -> Code {
+        Code::new(symbols).unwrap()
+    }
+
+    #[test]
+    fn packs_and_unpacks_symbols() {
+        let symbols = [15, 0, 7, 3, 3];
+        assert_eq!(code(&symbols).to_vec(), symbols);
+        assert_eq!(code(&[]).len(), 0);
+        assert!(Code::new(&[16]).is_none());
+        assert!(Code::new(&[0; MAX_PEGS + 1]).is_none());
+        // Orders like the symbol slices
+        assert!(code(&[0, 9, 9]) < code(&[1, 0, 0]));
+    }
+
+    #[test]
+    fn scoring_matches_the_unpacked_functions() {
+        let settings = BoardSettings::default().symbols(4).code_length(4);
+        let space: Vec<Vec<u8>> = CodeSpace::new(&settings).iter().collect();
+        for secret in &space {
+            for guess in space.iter().step_by(7) {
+                let (packed, packed_guess) = (code(secret), code(guess));
+                let expected = score(secret, guess);
+                assert_eq!(packed.score(&packed_guess), expected);
+                assert_eq!(packed.exact_matches(&packed_guess), expected.exact);
+                let index = verdicts(secret, guess)
+                    .iter()
+                    .fold(0, |index, &verdict| index * 3 + verdict as usize);
+                assert_eq!(packed.verdict_index(&packed_guess), index);
+            }
+        }
+    }
+
+    #[test]
+    fn exact_matches_on_full_length_codes() {
+        let a = code(&[0; MAX_PEGS]);
+        let mut symbols = [0; MAX_PEGS];
+        symbols[3] = 15;
+        symbols[MAX_PEGS - 1] = 1;
+        assert_eq!(a.exact_matches(&a), MAX_PEGS as u8);
+        assert_eq!(a.exact_matches(&code(&symbols)), MAX_PEGS as u8 - 2);
+    }
+}
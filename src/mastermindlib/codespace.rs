@@ -0,0 +1,356 @@
+use super::board::BoardSettings;
+use super::code::Code;
+use rand::Rng;
+use rand::seq::index;
+
+// Every code the settings allow, enumerated in lexicographic order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeSpace {
+    pub symbols: usize,
+    pub code_length: usize,
+    pub allow_repeats: bool,
+    // The last symbol is the blank, it may repeat even without `allow_repeats`
+    pub allow_blanks: bool,
+}
+
+impl CodeSpace {
+    pub fn new(settings: &BoardSettings) -> Self {
+        Self {
+            symbols: settings.symbol_count(),
+            code_length: settings.code_length as usize,
+            allow_repeats: settings.allow_repeats,
+            allow_blanks: settings.allow_blanks,
+        }
+    }
+
+    // Number of codes, saturates for huge spaces
+    pub fn len(&self) -> u128 {
+        self.completions(self.code_length, 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Ways to fill `remaining` positions once `used` colors are taken, saturates
+    fn completions(&self, remaining: usize, used: usize) -> u128 {
+        if self.allow_repeats {
+            return (self.symbols as u128).saturating_pow(remaining as u32);
+        }
+        let colors = self.colors().saturating_sub(used) as u128;
+        let permutations = |length: usize| {
+            (0..length as u128).fold(1, |product: u128, i| {
+                product.saturating_mul(colors.saturating_sub(i))
+            })
+        };
+        if !self.repeats_blank() {
+            return permutations(remaining);
+        }
+        // Sum over the number of blanks, they can sit on any of the positions
+        let mut positions = 1u128;
+        let mut total = 0u128;
+        for blanks in 0..=remaining {
+            total =
+                total.saturating_add(positions.saturating_mul(permutations(remaining - blanks)));
+            positions =
+                positions.saturating_mul((remaining - blanks) as u128) / (blanks as u128 + 1);
+        }
+        total
+    }
+
+    // Symbols that may appear only once without `allow_repeats`
+    fn colors(&self) -> usize {
+        self.symbols - usize::from(self.repeats_blank())
+    }
+
+    fn repeats_blank(&self) -> bool {
+        !self.allow_repeats && self.allow_blanks && self.symbols > 0
+    }
+
+    fn is_blank(&self, symbol: u8) -> bool {
+        self.repeats_blank() && symbol as usize == self.symbols - 1
+    }
+
+    // Position of `code` in the lexicographic order of this space, the inverse of `unrank`.
+    // Only meaningful for spaces that `Code::supports`
+    pub fn rank(&self, code: &Code) -> u64 {
+        if self.allow_repeats {
+            return (0..self.code_length).fold(0, |rank, position| {
+                rank * self.symbols as u64 + code.get(position) as u64
+            });
+        }
+        let mut used = 0usize;
+        let mut rank = 0u64;
+        let mut taken = 0u32;
+        for position in 0..self.code_length {
+            let symbol = code.get(position);
+            // Count the choices that sort before `symbol`, each of them takes a color
+            let smaller = if self.is_blank(symbol) {
+                self.colors() - used
+            } else {
+                let smaller_free = !taken & ((1 << symbol) - 1);
+                taken |= 1 << symbol;
+                smaller_free.count_ones() as usize
+            };
+            rank +=
+                smaller as u64 * self.completions(self.code_length - position - 1, used + 1) as u64;
+            used += usize::from(!self.is_blank(symbol));
+        }
+        rank
+    }
+
+    pub fn unrank(&self, mut rank: u64) -> Code {
+        let mut symbols = [0u8; crate::mastermindlib::code::MAX_PEGS];
+        if self.allow_repeats {
+            for position in (0..self.code_length).rev() {
+                symbols[position] = (rank % self.symbols as u64) as u8;
+                rank /= self.symbols as u64;
+            }
+        } else {
+            let mut used = 0usize;
+            let mut taken = 0u32;
+            for (position, slot) in symbols[..self.code_length].iter_mut().enumerate() {
+                let block = self.completions(self.code_length - position - 1, used + 1) as u64;
+                let free = (self.colors() - used) as u64;
+                *slot = if rank < free * block {
+                    // The digit-th color that is still free
+                    let digit = (rank / block) as usize;
+                    rank %= block;
+                    let symbol = (0..self.colors() as u32)
+                        .filter(|symbol| taken & (1 << symbol) == 0)
+                        .nth(digit)
+                        .expect("rank out of range");
+                    taken |= 1 << symbol;
+                    used += 1;
+                    symbol as u8
+                } else {
+                    assert!(self.repeats_blank(), "rank out of range");
+                    rank -= free * block;
+                    (self.symbols - 1) as u8
+                };
+            }
+        }
+        Code::new(&symbols[..self.code_length]).expect("space too large for Code")
+    }
+
+    // A code drawn uniformly from the space, which must not be empty
+    pub fn random_code<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<u8> {
+        assert!(!self.is_empty(), "no code fits these settings");
+        if self.allow_repeats {
+            return (0..self.code_length)
+                .map(|_| rng.random_range(0..=(self.symbols - 1) as u8))
+                .collect();
+        }
+        if !self.repeats_blank() {
+            // Draw without replacement
+            return index::sample(rng, self.symbols, self.code_length)
+                .into_iter()
+                .map(|symbol| symbol as u8)
+                .collect();
+        }
+        // Pick how many blanks by how many codes have that many, then where they go and
+        // which distinct colors fill the rest. Weighed in logs, the counts overflow
+        let length = self.code_length;
+        let logs: Vec<f64> = (0..=length)
+            .map(|blanks| {
+                let colored = length - blanks;
+                if colored > self.colors() {
+                    return f64::NEG_INFINITY;
+                }
+                let arrangements: f64 = (0..colored)
+                    .map(|i| ((self.colors() - i) as f64).ln())
+                    .sum();
+                let positions: f64 = (0..blanks)
+                    .map(|i| ((length - i) as f64 / (i + 1) as f64).ln())
+                    .sum();
+                arrangements + positions
+            })
+            .collect();
+        let largest = logs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = logs.iter().map(|log| (log - largest).exp()).collect();
+        let mut pick = rng.random::<f64>() * weights.iter().sum::<f64>();
+        let blanks = weights
+            .iter()
+            .position(|&weight| {
+                pick -= weight;
+                pick < 0.0
+            })
+            .unwrap_or_else(|| weights.iter().rposition(|&weight| weight > 0.0).unwrap());
+
+        let mut code = vec![(self.symbols - 1) as u8; length];
+        let mut colors = index::sample(rng, self.colors(), length - blanks).into_iter();
+        let blank_positions = index::sample(rng, length, blanks).into_vec();
+        for (position, slot) in code.iter_mut().enumerate() {
+            if !blank_positions.contains(&position) {
+                *slot = colors.next().expect("a color per position") as u8;
+            }
+        }
+        code
+    }
+
+    // Every code as a packed `Code`, only for spaces that `Code::supports`
+    pub fn codes(&self) -> impl Iterator<Item = Code> + '_ {
+        (0..self.len() as u64).map(|rank| self.unrank(rank))
+    }
+
+    pub fn iter(&self) -> Codes {
+        let mut first = Vec::with_capacity(self.code_length);
+        let next = self.fill(&mut first).then_some(first);
+        Codes { space: *self, next }
+    }
+
+    // Appends the smallest allowed symbols until the code is complete
+    fn fill(&self, code: &mut Vec<u8>) -> bool {
+        while code.len() < self.code_length {
+            match self.next_symbol(&code[..], 0) {
+                Some(symbol) => code.push(symbol),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    // Smallest symbol >= `from` that may follow `prefix`
+    fn next_symbol(&self, prefix: &[u8], from: usize) -> Option<u8> {
+        (from..self.symbols)
+            .map(|symbol| symbol as u8)
+            .find(|&symbol| {
+                self.allow_repeats || self.is_blank(symbol) || !prefix.contains(&symbol)
+            })
+    }
+
+    // Moves `code` to its successor, returns false after the last code
+    fn advance(&self, code: &mut Vec<u8>) -> bool {
+        while let Some(last) = code.pop() {
+            if let Some(symbol) = self.next_symbol(code, last as usize + 1) {
+                code.push(symbol);
+                if self.fill(code) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+pub struct Codes {
+    space: CodeSpace,
+    next: Option<Vec<u8>>,
+}
+
+impl Iterator for Codes {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let current = self.next.take()?;
+        let mut successor = current.clone();
+        if self.space.advance(&mut successor) {
+            self.next = Some(successor);
+        }
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn space(symbols: usize, code_length: usize, allow_repeats: bool) -> CodeSpace {
+        CodeSpace {
+            symbols,
+            code_length,
+            allow_repeats,
+            allow_blanks: false,
+        }
+    }
+
+    fn with_blanks(symbols: usize, code_length: usize, allow_repeats: bool) -> CodeSpace {
+        CodeSpace {
+            allow_blanks: true,
+            ..space(symbols, code_length, allow_repeats)
+        }
+    }
+
+    #[test]
+    fn counts_codes() {
+        assert_eq!(space(6, 4, true).len(), 1296);
+        assert_eq!(space(6, 4, false).len(), 360);
+        assert_eq!(space(3, 4, false).len(), 0);
+        assert_eq!(space(16, 40, true).len(), u128::MAX);
+    }
+
+    #[test]
+    fn rank_and_unrank_round_trip() {
+        for allow_repeats in [true, false] {
+            let space = space(5, 4, allow_repeats);
+            for rank in 0..space.len() as u64 {
+                let code = space.unrank(rank);
+                assert_eq!(space.rank(&code), rank, "{code:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn ranks_follow_the_lexicographic_order_of_iter() {
+        for allow_repeats in [true, false] {
+            let space = space(4, 3, allow_repeats);
+            let listed: Vec<Vec<u8>> = space.iter().collect();
+            let ranked: Vec<Vec<u8>> = space.codes().map(|code| code.to_vec()).collect();
+            assert_eq!(listed.len() as u128, space.len());
+            assert_eq!(listed, ranked);
+            assert!(listed.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        let no_repeats = space(4, 3, false);
+        assert!(no_repeats.iter().all(|code| {
+            let mut sorted = code.clone();
+            sorted.sort_unstable();
+            sorted.windows(2).all(|pair| pair[0] != pair[1])
+        }));
+        assert_eq!(no_repeats.unrank(0).to_vec(), [0, 1, 2]);
+        assert_eq!(no_repeats.unrank(23).to_vec(), [3, 2, 1]);
+    }
+
+    #[test]
+    fn blanks_repeat_without_repeats() {
+        // Three colors and the blank 3
+        let blanks = with_blanks(4, 3, false);
+        let listed: Vec<Vec<u8>> = blanks.iter().collect();
+        assert!(listed.contains(&vec![3, 3, 3]));
+        assert!(listed.contains(&vec![0, 3, 3]));
+        assert!(!listed.contains(&vec![0, 0, 3]));
+        // No blank, one on any of 3 positions, two or three of them
+        assert_eq!(blanks.len(), 6 + 3 * 6 + 3 * 3 + 1);
+        assert_eq!(listed.len() as u128, blanks.len());
+        let ranked: Vec<Vec<u8>> = blanks.codes().map(|code| code.to_vec()).collect();
+        assert_eq!(listed, ranked);
+        for code in blanks.codes() {
+            assert_eq!(blanks.unrank(blanks.rank(&code)), code);
+        }
+        // More pegs than colors still fit with enough blanks, two colors here
+        assert_eq!(with_blanks(3, 4, false).len(), 6 * 2 + 4 * 2 + 1);
+        assert_eq!(with_blanks(5, 4, true).len(), space(5, 4, true).len());
+    }
+
+    #[test]
+    fn random_codes_are_drawn_evenly() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        for space in [
+            space(4, 3, true),
+            space(5, 3, false),
+            with_blanks(5, 3, false),
+            with_blanks(3, 4, false),
+        ] {
+            let mut counts = std::collections::HashMap::new();
+            let draws = space.len() as usize * 300;
+            for _ in 0..draws {
+                *counts.entry(space.random_code(&mut rng)).or_insert(0) += 1;
+            }
+            assert_eq!(counts.len() as u128, space.len(), "{space:?}");
+            assert!(
+                counts.values().all(|&count| (200..400).contains(&count)),
+                "{space:?}"
+            );
+        }
+    }
+}
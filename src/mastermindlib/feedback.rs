@@ -0,0 +1,144 @@
+// Answer of the codemaker to a single guess
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize,
+)]
+pub struct Feedback {
+    // Right symbol in the right position (black peg)
+    pub exact: u8,
+    // Right symbol in the wrong position (white peg)
+    pub misplaced: u8,
+}
+
+// Scores a guess against a code without allocating, both slices should have the same length
+pub fn score(code: &[u8], guess: &[u8]) -> Feedback {
+    // Positive counts are unmatched code symbols, negative ones unmatched guess symbols
+    let mut balance = [0i16; 256];
+    let mut exact = 0;
+    let mut misplaced = 0;
+
+    for (&c, &g) in code.iter().zip(guess) {
+        if c == g {
+            exact += 1;
+            continue;
+        }
+        if balance[c as usize] < 0 {
+            misplaced += 1;
+        }
+        balance[c as usize] += 1;
+        if balance[g as usize] > 0 {
+            misplaced += 1;
+        }
+        balance[g as usize] -= 1;
+    }
+
+    Feedback { exact, misplaced }
+}
+
+// What the codemaker tells about a guess
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize,
+)]
+pub enum FeedbackMode {
+    // Number of exact and misplaced pegs, classic Mastermind
+    #[default]
+    Counts,
+    // A verdict for every peg of the guess like in Wordle, easier for new players
+    Positional,
+    // Only the number of exact pegs, misplaced ones aren't reported. A harder variant
+    ExactOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Verdict {
+    Correct,
+    // In the code but somewhere else
+    Present,
+    Absent,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Correct => write!(f, "correct"),
+            Verdict::Present => write!(f, "present"),
+            Verdict::Absent => write!(f, "absent"),
+        }
+    }
+}
+
+// Verdicts the way Wordle gives them: exact matches first, then a repeated symbol is only
+// marked present as often as the code has unmatched copies of it, from left to right.
+// The number of correct and present verdicts is the same as `score` counts
+pub fn verdicts(code: &[u8], guess: &[u8]) -> Vec<Verdict> {
+    let mut unmatched = [0u8; 256];
+    for (&c, &g) in code.iter().zip(guess) {
+        if c != g {
+            unmatched[c as usize] += 1;
+        }
+    }
+    code.iter()
+        .zip(guess)
+        .map(|(&c, &g)| {
+            if c == g {
+                Verdict::Correct
+            } else if unmatched[g as usize] > 0 {
+                unmatched[g as usize] -= 1;
+                Verdict::Present
+            } else {
+                Verdict::Absent
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feedback(exact: u8, misplaced: u8) -> Feedback {
+        Feedback { exact, misplaced }
+    }
+
+    #[test]
+    fn scores_known_cases() {
+        assert_eq!(score(&[0, 1, 2, 3], &[0, 1, 2, 3]), feedback(4, 0));
+        assert_eq!(score(&[0, 1, 2, 3], &[3, 2, 1, 0]), feedback(0, 4));
+        assert_eq!(score(&[0, 1, 2, 3], &[4, 5, 4, 5]), feedback(0, 0));
+        assert_eq!(score(&[0, 1, 2, 3], &[0, 2, 4, 5]), feedback(1, 1));
+    }
+
+    #[test]
+    fn repeated_symbols_match_once() {
+        // One 0 is exact, the code has no second 0 for the other copies
+        assert_eq!(score(&[0, 1, 2, 3], &[0, 0, 0, 0]), feedback(1, 0));
+        // The code's two 1s can't both be matched by a single 1 in the guess
+        assert_eq!(score(&[1, 1, 2, 2], &[3, 3, 1, 4]), feedback(0, 1));
+        assert_eq!(score(&[1, 1, 2, 2], &[2, 2, 1, 1]), feedback(0, 4));
+        assert_eq!(score(&[0, 0, 1, 1], &[0, 1, 0, 2]), feedback(1, 2));
+        assert_eq!(score(&[5, 5, 5, 1], &[1, 5, 5, 5]), feedback(2, 2));
+    }
+
+    #[test]
+    fn score_is_symmetric() {
+        let codes = [[0, 0, 1, 1], [1, 2, 3, 0], [2, 2, 2, 1], [3, 1, 1, 0]];
+        for code in &codes {
+            for guess in &codes {
+                assert_eq!(score(code, guess), score(guess, code));
+            }
+        }
+    }
+
+    #[test]
+    fn verdicts_mark_repeats_present_only_as_often_as_the_code_has_them() {
+        use Verdict::{Absent, Correct, Present};
+        // The only 1 of the code is already matched exactly
+        assert_eq!(
+            verdicts(&[0, 1, 2, 3], &[1, 1, 0, 0]),
+            vec![Absent, Correct, Present, Absent]
+        );
+        assert_eq!(
+            verdicts(&[0, 0, 1, 2], &[3, 0, 0, 0]),
+            vec![Absent, Correct, Present, Absent]
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use super::board::{BoardSettings, Guess};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum HintKind {
+    SuggestGuess,
+    RevealPeg,
+    EliminateColor,
+    CountRemaining,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Hint {
+    // A strong next guess picked by a solver
+    Suggestion(Guess),
+    // The secret has `symbol` at `position`
+    Peg { position: usize, symbol: u8 },
+    // The secret doesn't use this symbol anywhere
+    Absent(u8),
+    // Number of codes that still fit every answer
+    Remaining(u64),
+}
+
+impl Hint {
+    pub fn kind(&self) -> HintKind {
+        match self {
+            Hint::Suggestion(_) => HintKind::SuggestGuess,
+            Hint::Peg { .. } => HintKind::RevealPeg,
+            Hint::Absent(_) => HintKind::EliminateColor,
+            Hint::Remaining(_) => HintKind::CountRemaining,
+        }
+    }
+
+    // The hint in the settings' alphabet, positions count from 1 like turns do
+    pub fn message(&self, settings: &BoardSettings) -> String {
+        match self {
+            Hint::Suggestion(guess) => format!("try {}", settings.format_code(&guess.0)),
+            Hint::Peg { position, symbol } => format!(
+                "symbol {} is at position {}",
+                settings.format_code(&[*symbol]),
+                position + 1
+            ),
+            Hint::Absent(symbol) => format!(
+                "symbol {} is not in the code",
+                settings.format_code(&[*symbol])
+            ),
+            Hint::Remaining(1) => "only one code is still possible".to_string(),
+            Hint::Remaining(count) => format!("{count} codes are still possible"),
+        }
+    }
+}
+
+// A hint as stored in the board history, `turn` is the number of guesses made before it
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct HintRecord {
+    pub turn: usize,
+    pub hint: Hint,
+}
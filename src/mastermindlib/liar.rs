@@ -0,0 +1,200 @@
+use super::board::{BoardSettings, BoardState};
+use super::code::Code;
+use super::codespace::CodeSpace;
+use super::feedback::{FeedbackMode, score, verdicts};
+use super::solver::{AnswerIndex, is_exhaustive};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// The adversary checks every code for every answer, larger spaces get random lies
+const MAX_ADVERSARY_CODES: u128 = 1 << 16;
+
+// Random codes drawn while looking for one that answers differently
+const MAX_DRAWS: usize = 64;
+
+// How the codemaker picks which answers are false, with `BoardSettings::lies` above zero
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize,
+)]
+pub enum Liar {
+    // Lies on random turns spread over the whole game, with the answer of a random code
+    #[default]
+    Random,
+    // Picks the answer, true or not, that keeps the most codes possible
+    Adversarial,
+}
+
+// A lie answers the guess as if the secret were another code. Returns that code, or None
+// for a truthful answer. A correct guess is never lied about and no lie claims a win
+pub(crate) fn stand_in(
+    settings: &BoardSettings,
+    state: &BoardState,
+    guess: &[u8],
+) -> Option<Vec<u8>> {
+    let lies_left = (settings.lies as usize).saturating_sub(state.lied.len());
+    if lies_left == 0 || guess == state.code.as_slice() {
+        return None;
+    }
+    let adversarial = settings.liar == Liar::Adversarial
+        && is_exhaustive(settings)
+        && settings.code_space_size() <= MAX_ADVERSARY_CODES;
+    if adversarial {
+        adversarial_stand_in(settings, state, guess)
+    } else {
+        random_stand_in(settings, state, guess, lies_left)
+    }
+}
+
+fn random_stand_in(
+    settings: &BoardSettings,
+    state: &BoardState,
+    guess: &[u8],
+    lies_left: usize,
+) -> Option<Vec<u8>> {
+    // Seeded by the game and the turn, so replays tell the same lies
+    let turn = state.guesses.len();
+    let seed = state.seed.unwrap_or_else(|| {
+        state.code.iter().fold(0u64, |seed, &s| {
+            seed.wrapping_mul(31).wrapping_add(s as u64)
+        })
+    });
+    let mut rng =
+        StdRng::seed_from_u64(seed ^ (turn as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+
+    // Every remaining try is equally likely to get one of the remaining lies
+    let tries_left = (settings.total_tries() as usize)
+        .saturating_sub(turn)
+        .max(1);
+    if !rng.random_ratio(lies_left.min(tries_left) as u32, tries_left as u32) {
+        return None;
+    }
+    (0..MAX_DRAWS)
+        .map(|_| BoardState::with_rng(settings, &mut rng).code)
+        .find(|other| other != guess && !same_answer(settings, other, &state.code, guess))
+}
+
+fn adversarial_stand_in(
+    settings: &BoardSettings,
+    state: &BoardState,
+    guess: &[u8],
+) -> Option<Vec<u8>> {
+    let answers = AnswerIndex::new(settings);
+    let guess = Code::new(guess)?;
+    let history: Vec<(Code, usize)> = state
+        .guesses
+        .iter()
+        .enumerate()
+        .filter_map(|(turn, guess)| Some((Code::new(&guess.0)?, answers.recorded(state, turn))))
+        .collect();
+
+    // Codes with a lie to spare stay possible whatever the answer, the others only
+    // with their own answer
+    let mut spare = 0u64;
+    let mut possible = vec![0u64; answers.count()];
+    let mut stand_ins: Vec<Option<Code>> = vec![None; answers.count()];
+    for code in CodeSpace::new(settings).codes() {
+        let answer = answers.of(&code, &guess);
+        // Guessed codes are ruled out, a correct guess would have been confirmed
+        if code == guess || history.iter().any(|(guess, _)| *guess == code) {
+            continue;
+        }
+        stand_ins[answer].get_or_insert(code);
+        let mismatches = history
+            .iter()
+            .filter(|(guess, recorded)| answers.of(&code, guess) != *recorded)
+            .count();
+        if mismatches < settings.lies as usize {
+            spare += 1;
+        } else if mismatches == settings.lies as usize {
+            possible[answer] += 1;
+        }
+    }
+
+    let secret = Code::new(&state.code)?;
+    let truth = answers.of(&secret, &guess);
+    // Ties go to the truth, lies are only told when they help
+    let (best, _) = possible
+        .iter()
+        .enumerate()
+        .filter(|&(answer, _)| answer == truth || stand_ins[answer].is_some())
+        .max_by_key(|&(answer, &count)| (spare + count, answer == truth))?;
+    if best == truth {
+        return None;
+    }
+    stand_ins[best].map(|code| code.to_vec())
+}
+
+fn same_answer(settings: &BoardSettings, code: &[u8], other: &[u8], guess: &[u8]) -> bool {
+    match settings.feedback {
+        FeedbackMode::Counts => score(code, guess) == score(other, guess),
+        FeedbackMode::Positional => verdicts(code, guess) == verdicts(other, guess),
+        FeedbackMode::ExactOnly => score(code, guess).exact == score(other, guess).exact,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mastermindlib::board::{Guess, MastermindBoard};
+    use rand::seq::IndexedRandom;
+
+    // Plays random guesses until the board runs out of tries or the code is hit
+    fn play_randomly(settings: &BoardSettings, seed: u64) -> MastermindBoard {
+        let mut board = MastermindBoard::with_seed(settings.clone(), seed);
+        let codes: Vec<Vec<u8>> = CodeSpace::new(settings).iter().collect();
+        let mut rng = StdRng::seed_from_u64(seed);
+        while !board.is_over() {
+            let guess = Guess(codes.choose(&mut rng).unwrap().clone());
+            board.try_guess(&guess).unwrap();
+        }
+        board
+    }
+
+    fn liars() -> Vec<BoardSettings> {
+        [Liar::Random, Liar::Adversarial]
+            .into_iter()
+            .map(|liar| {
+                BoardSettings::default()
+                    .symbols(4)
+                    .code_length(3)
+                    .max_tries(8)
+                    .lies(2)
+                    .liar(liar)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lies_are_limited_and_never_claim_a_win() {
+        for settings in liars() {
+            let mut lies_told = 0;
+            for seed in 0..200 {
+                let board = play_randomly(&settings, seed);
+                let state = &board.state;
+                assert!(state.lied.len() <= settings.lies as usize);
+                lies_told += state.lied.len();
+                for (turn, guess) in state.guesses.iter().enumerate() {
+                    let truth = score(&state.code, &guess.0);
+                    let answer = state.answers[turn];
+                    assert_eq!(state.lied.contains(&turn), answer != truth, "turn {turn}");
+                    if guess.0 != state.code {
+                        assert!(usize::from(answer.exact) < state.code.len());
+                    }
+                }
+            }
+            assert!(lies_told > 0, "{:?} never lied", settings.liar);
+        }
+    }
+
+    #[test]
+    fn replays_tell_the_same_lies() {
+        for settings in liars() {
+            for seed in 0..50 {
+                let board = play_randomly(&settings, seed);
+                let replayed = board.replay().unwrap();
+                assert_eq!(replayed.state.lied, board.state.lied);
+                assert_eq!(replayed.state.answers, board.state.answers);
+            }
+        }
+    }
+}
@@ -1 +1,9 @@
 pub mod board;
+pub mod code;
+pub mod codespace;
+pub mod feedback;
+pub mod hint;
+pub mod liar;
+pub mod palette;
+pub mod save;
+pub mod solver;
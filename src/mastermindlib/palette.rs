@@ -0,0 +1,93 @@
+use rgb::RGB8;
+
+// The first colors handed out, the rest is generated
+const COLORS: [RGB8; 9] = [
+    RGB8 {
+        r: 241,
+        g: 196,
+        b: 15,
+    },
+    RGB8 {
+        r: 26,
+        g: 188,
+        b: 156,
+    },
+    RGB8 {
+        r: 52,
+        g: 152,
+        b: 219,
+    },
+    RGB8 {
+        r: 155,
+        g: 89,
+        b: 182,
+    },
+    RGB8 {
+        r: 192,
+        g: 57,
+        b: 43,
+    },
+    RGB8 {
+        r: 243,
+        g: 156,
+        b: 18,
+    },
+    RGB8 {
+        r: 22,
+        g: 160,
+        b: 133,
+    },
+    RGB8 {
+        r: 41,
+        g: 128,
+        b: 185,
+    },
+    RGB8 {
+        r: 142,
+        g: 68,
+        b: 173,
+    },
+];
+
+// Presentation of the symbols of a game, the rules only know the symbol count
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Palette {
+    pub colors: Vec<RGB8>,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<RGB8>) -> Self {
+        Self { colors }
+    }
+
+    // Uses the built-in colors and spreads extra hues around the color wheel beyond them
+    pub fn for_symbols(symbols: u8) -> Self {
+        let colors = (0..symbols as usize)
+            .map(|i| match COLORS.get(i) {
+                Some(&color) => color,
+                None => hue_to_rgb((i - COLORS.len()) as f32 * 137.508),
+            })
+            .collect();
+        Self { colors }
+    }
+
+    pub fn color(&self, symbol: u8) -> Option<RGB8> {
+        self.colors.get(symbol as usize).copied()
+    }
+}
+
+// Fully saturated color for a hue in degrees, slightly darkened to match the built-in colors
+fn hue_to_rgb(hue: f32) -> RGB8 {
+    let h = (hue % 360.0) / 60.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as u8 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let scale = |c: f32| (40.0 + c * 180.0) as u8;
+    RGB8::new(scale(r), scale(g), scale(b))
+}
@@ -0,0 +1,323 @@
+use super::board::{Alphabet, BoardSettings, BoardState, MastermindBoard, Phase};
+use super::feedback::{Feedback, FeedbackMode};
+use super::liar::Liar;
+use serde::Deserialize;
+use std::fmt;
+
+// Bump this and add a step to `migrate` whenever the saved layout changes
+pub const SAVE_VERSION: u32 = 9;
+
+// On-disk layout of a `MastermindBoard`, every board is (de)serialized through this
+#[derive(serde::Deserialize, serde::Serialize)]
+pub(crate) struct SavedBoard {
+    version: u32,
+    settings: BoardSettings,
+    state: BoardState,
+    // Added in version 2
+    #[serde(default)]
+    phase: Option<Phase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveError {
+    UnsupportedVersion(u32),
+    InvalidCode,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "unsupported save version {version} (expected at most {SAVE_VERSION})"
+                )
+            }
+            SaveError::InvalidCode => write!(f, "the saved code does not match the saved settings"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+// Upgrades an older save one version at a time until it matches SAVE_VERSION
+fn migrate(mut saved: SavedBoard) -> Result<SavedBoard, SaveError> {
+    loop {
+        match saved.version {
+            1 => {
+                saved.phase = Some(Phase::from_history(&saved.settings, &saved.state));
+                saved.version = 2;
+            }
+            // Answers changed from `GameState` to `Feedback`, see `deserialize_answers`
+            2 => saved.version = 3,
+            // Settings store a symbol count instead of colors, see `SavedSettings`
+            3 => saved.version = 4,
+            // Hints were added, older games didn't use any
+            4 => saved.version = 5,
+            // Settings gained an alphabet, older saves show colors
+            5 => saved.version = 6,
+            // Settings gained a feedback mode and states verdicts, older saves count pegs
+            6 => saved.version = 7,
+            // Static Mastermind added a batch size and pending guesses, older saves have none
+            7 => saved.version = 8,
+            // The lying codemaker was added, older saves only have true answers
+            8 => saved.version = 9,
+            SAVE_VERSION => return Ok(saved),
+            version => return Err(SaveError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+impl From<MastermindBoard> for SavedBoard {
+    fn from(board: MastermindBoard) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            settings: board.settings,
+            state: board.state,
+            phase: Some(board.phase),
+        }
+    }
+}
+
+impl TryFrom<SavedBoard> for MastermindBoard {
+    type Error = SaveError;
+
+    fn try_from(saved: SavedBoard) -> Result<Self, Self::Error> {
+        let saved = migrate(saved)?;
+        if saved.settings.validate_code(&saved.state.code).is_err() {
+            return Err(SaveError::InvalidCode);
+        }
+        Ok(Self {
+            settings: saved.settings,
+            state: saved.state,
+            phase: saved.phase.unwrap_or_default(),
+        })
+    }
+}
+
+// Before version 3 answers were stored as `GameState::GuessAnswer(exact, misplaced)`
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum SavedAnswer {
+    Feedback(Feedback),
+    Legacy(LegacyAnswer),
+}
+
+#[derive(serde::Deserialize)]
+enum LegacyAnswer {
+    GuessAnswer(u8, u8),
+}
+
+pub(crate) fn deserialize_answers<'de, D>(deserializer: D) -> Result<Vec<Feedback>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let answers = Vec::<SavedAnswer>::deserialize(deserializer)?;
+    Ok(answers
+        .into_iter()
+        .map(|answer| match answer {
+            SavedAnswer::Feedback(feedback) => feedback,
+            SavedAnswer::Legacy(LegacyAnswer::GuessAnswer(exact, misplaced)) => {
+                Feedback { exact, misplaced }
+            }
+        })
+        .collect())
+}
+
+// Before version 4 settings carried the RGB colors, only their number mattered
+#[derive(serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct SavedSettings {
+    // Zero when missing, options would need an explicit `Some` in RON
+    symbols: u8,
+    colors: Vec<serde::de::IgnoredAny>,
+    code_length: u8,
+    max_tries: u8,
+    allow_repeats: bool,
+    allow_blanks: bool,
+    alphabet: Alphabet,
+    feedback: FeedbackMode,
+    static_guesses: u8,
+    lies: u8,
+    liar: Liar,
+}
+
+impl Default for SavedSettings {
+    fn default() -> Self {
+        let settings = BoardSettings::default();
+        Self {
+            symbols: 0,
+            colors: Vec::new(),
+            code_length: settings.code_length,
+            max_tries: settings.max_tries,
+            allow_repeats: settings.allow_repeats,
+            allow_blanks: settings.allow_blanks,
+            alphabet: settings.alphabet,
+            feedback: settings.feedback,
+            static_guesses: settings.static_guesses,
+            lies: settings.lies,
+            liar: settings.liar,
+        }
+    }
+}
+
+impl From<SavedSettings> for BoardSettings {
+    fn from(saved: SavedSettings) -> Self {
+        let symbols = match (saved.symbols, saved.colors.len()) {
+            (0, 0) => BoardSettings::default().symbols,
+            (0, colors) => colors as u8,
+            (symbols, _) => symbols,
+        };
+        Self {
+            symbols,
+            code_length: saved.code_length,
+            max_tries: saved.max_tries,
+            allow_repeats: saved.allow_repeats,
+            allow_blanks: saved.allow_blanks,
+            alphabet: saved.alphabet,
+            feedback: saved.feedback,
+            static_guesses: saved.static_guesses,
+            lies: saved.lies,
+            liar: saved.liar,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mastermindlib::board::Guess;
+    use crate::mastermindlib::feedback::Verdict;
+    use crate::mastermindlib::hint::{Hint, HintRecord};
+
+    // Fixtures were written by the library at each save version, most of them hold the
+    // code 3141 with the guesses 0011 and 1123
+    fn load(fixture: &str) -> MastermindBoard {
+        serde_json::from_str(fixture).expect("fixture loads")
+    }
+
+    fn feedback(exact: u8, misplaced: u8) -> Feedback {
+        Feedback { exact, misplaced }
+    }
+
+    fn assert_classic_game(board: &MastermindBoard) {
+        assert_eq!(board.settings.symbols, 6);
+        assert_eq!(board.settings.code_length, 4);
+        assert_eq!(board.settings.max_tries, 10);
+        assert_eq!(board.state.code, [3, 1, 4, 1]);
+        assert_eq!(
+            board.state.guesses,
+            [Guess(vec![0, 0, 1, 1]), Guess(vec![1, 1, 2, 3])]
+        );
+        assert_eq!(board.state.answers, [feedback(1, 1), feedback(1, 2)]);
+        assert_eq!(board.phase(), Phase::InProgress);
+        assert_eq!(board.remaining_tries(), 8);
+    }
+
+    #[test]
+    fn loads_legacy_answers_and_colors() {
+        for fixture in [
+            include_str!("../../tests/saves/v1.json"),
+            include_str!("../../tests/saves/v2.json"),
+            include_str!("../../tests/saves/v3.json"),
+        ] {
+            let board = load(fixture);
+            assert_classic_game(&board);
+            assert_eq!(board.settings, BoardSettings::default());
+        }
+    }
+
+    #[test]
+    fn loads_symbol_counts() {
+        let board = load(include_str!("../../tests/saves/v4.json"));
+        assert_classic_game(&board);
+        assert!(board.state.hints.is_empty());
+    }
+
+    #[test]
+    fn loads_hints() {
+        let board = load(include_str!("../../tests/saves/v5.json"));
+        assert_classic_game(&board);
+        assert_eq!(
+            board.state.hints,
+            [HintRecord {
+                turn: 2,
+                hint: Hint::Peg {
+                    position: 0,
+                    symbol: 3
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn loads_alphabet() {
+        let board = load(include_str!("../../tests/saves/v6.json"));
+        assert_eq!(board.settings, BoardSettings::bulls_and_cows());
+        assert_eq!(board.state.code, [3, 1, 4, 5]);
+        assert_eq!(board.state.answers, [feedback(1, 1), feedback(0, 2)]);
+        assert_eq!(board.settings.feedback, FeedbackMode::Counts);
+    }
+
+    #[test]
+    fn loads_verdicts() {
+        use Verdict::{Absent, Correct, Present};
+        let board = load(include_str!("../../tests/saves/v7.json"));
+        assert_classic_game(&board);
+        assert_eq!(board.settings.feedback, FeedbackMode::Positional);
+        assert_eq!(
+            board.state.verdicts,
+            [
+                vec![Absent, Absent, Present, Correct],
+                vec![Present, Correct, Absent, Present]
+            ]
+        );
+        assert_eq!(board.settings.static_guesses, 0);
+    }
+
+    #[test]
+    fn loads_pending_batch() {
+        let board = load(include_str!("../../tests/saves/v8.json"));
+        assert_eq!(board.settings.static_guesses, 3);
+        assert!(board.state.guesses.is_empty());
+        assert_eq!(board.state.pending.len(), 2);
+        assert!(board.is_collecting_batch());
+        assert_eq!(board.remaining_tries(), 2);
+        assert_eq!(board.settings.lies, 0);
+    }
+
+    #[test]
+    fn loads_lies() {
+        let board = load(include_str!("../../tests/saves/v9.json"));
+        assert_eq!(board.settings.lies, 1);
+        assert_eq!(board.settings.liar, Liar::Random);
+        assert_eq!(board.state.guesses.len(), 3);
+        assert_eq!(board.state.lied, [1]);
+    }
+
+    #[test]
+    fn migrated_boards_save_at_the_current_version() {
+        let board = load(include_str!("../../tests/saves/v1.json"));
+        let saved = serde_json::to_value(&board).unwrap();
+        assert_eq!(saved["version"], SAVE_VERSION);
+        let reloaded: MastermindBoard = serde_json::from_value(saved).unwrap();
+        assert_classic_game(&reloaded);
+    }
+
+    #[test]
+    fn rejects_newer_versions_and_invalid_codes() {
+        let board = load(include_str!("../../tests/saves/v1.json"));
+        let mut saved = serde_json::to_value(&board).unwrap();
+        saved["version"] = (SAVE_VERSION + 1).into();
+        let err = serde_json::from_value::<MastermindBoard>(saved.clone()).unwrap_err();
+        assert!(
+            err.to_string().contains("unsupported save version"),
+            "{err}"
+        );
+
+        saved["version"] = SAVE_VERSION.into();
+        saved["state"]["code"] = serde_json::json!([3, 1, 4, 9]);
+        let err = serde_json::from_value::<MastermindBoard>(saved).unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err}");
+    }
+}
@@ -0,0 +1,295 @@
+use super::{
+    AnswerIndex, Codebreaker, SamplingSolver, SimpleSolver, is_exhaustive, opening_guesses,
+};
+use crate::mastermindlib::board::{BoardSettings, BoardState, Guess};
+use crate::mastermindlib::code::Code;
+use crate::mastermindlib::codespace::CodeSpace;
+use std::sync::{Mutex, OnceLock};
+
+// Size limit of the table that numbers the classes while refining them
+const MAX_CLASS_SLOTS: usize = 1 << 24;
+
+// A set of guesses whose answers, taken together, tell every code apart
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub guesses: Vec<Guess>,
+    // Whether every smaller size was ruled out. False when the search budget ran out
+    // first, the batch is then only an upper bound
+    pub proven_minimal: bool,
+}
+
+// Batches found so far with the settings and budgets they were searched with. `play`
+// starts a new solver for every game, the search takes a while
+type BatchCache = Mutex<Vec<(BoardSettings, u128, u64, Vec<Guess>)>>;
+
+// Strategy for Static Mastermind: plays the smallest batch it finds that identifies the
+// code, then the only code consistent with its answers. Also plays normal games, just
+// without adapting
+#[derive(Debug, Clone)]
+pub struct BatchSolver {
+    // Larger code spaces are refused, the solver then plays `SamplingSolver`
+    pub max_codes: u128,
+    // Partition refinements the search for a smaller batch may try
+    pub max_nodes: u64,
+    fallback: SamplingSolver,
+}
+
+impl Default for BatchSolver {
+    fn default() -> Self {
+        Self {
+            max_codes: 4096,
+            max_nodes: 200_000,
+            fallback: SamplingSolver::with_seed(0),
+        }
+    }
+}
+
+impl BatchSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_codes(mut self, max_codes: u128) -> Self {
+        self.max_codes = max_codes;
+        self
+    }
+
+    pub fn max_nodes(mut self, max_nodes: u64) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    // A greedy batch, then a search for smaller ones within `max_nodes`. The result is
+    // only minimal when `Batch::proven_minimal` says so, e.g. 3 pegs with 6 colors need
+    // 20 million nodes to rule out a batch of 4. None if the code space is too large or
+    // answers may be lies
+    pub fn identifying_batch(&self, settings: &BoardSettings) -> Option<Batch> {
+        if !is_exhaustive(settings)
+            || settings.code_space_size() > self.max_codes
+            || settings.lies > 0
+        {
+            return None;
+        }
+        let answers = AnswerIndex::new(settings);
+        let space: Vec<Code> = CodeSpace::new(settings).codes().collect();
+        if space.len().saturating_mul(answers.count()) > MAX_CLASS_SLOTS {
+            return None;
+        }
+        let mut search = BatchSearch {
+            ids: vec![u32::MAX; space.len() * answers.count()],
+            answers,
+            space,
+            openings: opening_guesses(settings),
+            nodes_left: self.max_nodes,
+        };
+
+        let mut best = search.greedy();
+        let mut proven_minimal = true;
+        // Every smaller size has to be ruled out, starting with the smallest that could work
+        for size in search.lower_bound()..best.len() {
+            match search.find(size) {
+                Some(batch) => {
+                    best = batch;
+                    break;
+                }
+                None if search.nodes_left == 0 => {
+                    proven_minimal = false;
+                    break;
+                }
+                None => {}
+            }
+        }
+        Some(Batch {
+            guesses: best.into_iter().map(Guess::from).collect(),
+            proven_minimal,
+        })
+    }
+
+    // The batch for these settings, only searched once per process
+    fn cached_batch(&self, settings: &BoardSettings) -> Vec<Guess> {
+        static CACHE: OnceLock<BatchCache> = OnceLock::new();
+        let cache = CACHE.get_or_init(Mutex::default);
+        let found = cache.lock().expect("batch cache").iter().find_map(
+            |(cached, max_codes, max_nodes, batch)| {
+                (cached.same_rules(settings)
+                    && *max_codes == self.max_codes
+                    && *max_nodes == self.max_nodes)
+                    .then(|| batch.clone())
+            },
+        );
+        found.unwrap_or_else(|| {
+            let batch = self
+                .identifying_batch(settings)
+                .map_or_else(Vec::new, |batch| batch.guesses);
+            cache.lock().expect("batch cache").push((
+                settings.clone(),
+                self.max_codes,
+                self.max_nodes,
+                batch.clone(),
+            ));
+            batch
+        })
+    }
+}
+
+struct BatchSearch {
+    answers: AnswerIndex,
+    space: Vec<Code>,
+    openings: Vec<Code>,
+    nodes_left: u64,
+    // Class id for every (class, answer) pair, u32::MAX where unused
+    ids: Vec<u32>,
+}
+
+impl BatchSearch {
+    // Codes split into classes by the answers so far, as a class id per code
+    fn refine(&mut self, classes: &[u32], guess: &Code) -> (Vec<u32>, usize) {
+        let answer_count = self.answers.count();
+        let mut used = Vec::new();
+        let refined = classes
+            .iter()
+            .zip(&self.space)
+            .map(|(&class, code)| {
+                let slot = class as usize * answer_count + self.answers.of(code, guess);
+                if self.ids[slot] == u32::MAX {
+                    self.ids[slot] = used.len() as u32;
+                    used.push(slot);
+                }
+                self.ids[slot]
+            })
+            .collect();
+        for &slot in &used {
+            self.ids[slot] = u32::MAX;
+        }
+        (refined, used.len())
+    }
+
+    // Each guess can at best multiply the number of classes by the number of answers
+    fn lower_bound(&self) -> usize {
+        let answers = self.answers.count().max(2) as u128;
+        let mut size = 0;
+        let mut reachable = 1u128;
+        while reachable < self.space.len() as u128 {
+            reachable = reachable.saturating_mul(answers);
+            size += 1;
+        }
+        size
+    }
+
+    // Repeatedly adds the guess that splits the codes into the most classes
+    fn greedy(&mut self) -> Vec<Code> {
+        let mut classes = vec![0u32; self.space.len()];
+        let mut count = 1;
+        let mut batch = Vec::new();
+        while count < self.space.len() {
+            let mut best = (0, Vec::new(), self.space[0]);
+            for index in 0..self.space.len() {
+                let guess = self.space[index];
+                let (refined, refined_count) = self.refine(&classes, &guess);
+                if refined_count > best.0 {
+                    best = (refined_count, refined, guess);
+                }
+            }
+            batch.push(best.2);
+            classes = best.1;
+            count = best.0;
+        }
+        batch
+    }
+
+    // A batch of exactly `size` guesses that identifies every code. Batches are sets, so
+    // guesses after the first are tried in ascending order. By symmetry the first one can
+    // be an opening pattern
+    fn find(&mut self, size: usize) -> Option<Vec<Code>> {
+        let classes = vec![0u32; self.space.len()];
+        let mut batch = Vec::with_capacity(size);
+        for opening in self.openings.clone() {
+            let (refined, count) = self.refine(&classes, &opening);
+            batch.push(opening);
+            if self.extend(&refined, count, size, 0, &mut batch) {
+                return Some(batch);
+            }
+            batch.pop();
+        }
+        None
+    }
+
+    fn extend(
+        &mut self,
+        classes: &[u32],
+        count: usize,
+        size: usize,
+        start: usize,
+        batch: &mut Vec<Code>,
+    ) -> bool {
+        if count == self.space.len() {
+            // Fill up with any guess, the batch already identifies the code
+            while batch.len() < size {
+                batch.push(batch[0]);
+            }
+            return true;
+        }
+        let left = (size - batch.len()) as u32;
+        let reachable =
+            (count as u128).saturating_mul((self.answers.count() as u128).saturating_pow(left));
+        if left == 0 || reachable < self.space.len() as u128 {
+            return false;
+        }
+        for index in start..self.space.len() {
+            if self.nodes_left == 0 {
+                return false;
+            }
+            self.nodes_left -= 1;
+            let guess = self.space[index];
+            let (refined, refined_count) = self.refine(classes, &guess);
+            if refined_count == count {
+                continue;
+            }
+            batch.push(guess);
+            if self.extend(&refined, refined_count, size, index + 1, batch) {
+                return true;
+            }
+            batch.pop();
+        }
+        false
+    }
+}
+
+impl Codebreaker for BatchSolver {
+    fn name(&self) -> &str {
+        "static-batch"
+    }
+
+    fn next_guess(&mut self, settings: &BoardSettings, state: &BoardState) -> Guess {
+        let batch = self.cached_batch(settings);
+
+        // A static board wants exactly its batch size, pad or cut the found batch
+        let batch_size = match settings.static_guesses {
+            0 => batch.len(),
+            size => size as usize,
+        };
+        if batch.is_empty() {
+            return self.fallback.next_guess(settings, state);
+        }
+        let played = state.guesses.len() + state.pending.len();
+        if played < batch_size {
+            // Players may enter the batch in any order or mix in their own guesses, so the
+            // first one not played yet comes next. Guesses past the found batch add
+            // nothing, any code not played yet fills in
+            let unplayed =
+                |guess: &Guess| !state.pending.contains(guess) && !state.guesses.contains(guess);
+            return batch
+                .iter()
+                .find(|guess| unplayed(guess))
+                .cloned()
+                .unwrap_or_else(|| {
+                    CodeSpace::new(settings)
+                        .iter()
+                        .map(Guess)
+                        .find(unplayed)
+                        .unwrap_or_else(|| batch[0].clone())
+                });
+        }
+        SimpleSolver.next_guess(settings, state)
+    }
+}
@@ -0,0 +1,153 @@
+use super::{Codebreaker, EntropySolver, KnuthSolver};
+use crate::mastermindlib::board::{BoardSettings, BoardState, Guess};
+use crate::mastermindlib::code::Code;
+use crate::mastermindlib::codespace::CodeSpace;
+use crate::mastermindlib::feedback::Feedback;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+// Regenerate with `cargo run --release --bin mastermind-bench -- --opening-book`
+const EMBEDDED: &str = include_str!("openings.json");
+
+// Precomputed first guesses, and second guesses for every answer to the first one. The
+// searching solvers take a while for these and they only depend on the settings
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(transparent)]
+pub struct OpeningBook {
+    pub entries: Vec<BookEntry>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct BookEntry {
+    // `Codebreaker::name` of the strategy that chose these guesses
+    pub strategy: String,
+    pub settings: BoardSettings,
+    pub first: Guess,
+    pub second: Vec<Reply>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Reply {
+    pub feedback: Feedback,
+    pub guess: Guess,
+}
+
+impl OpeningBook {
+    // The book shipped with the library
+    pub fn embedded() -> &'static OpeningBook {
+        static BOOK: OnceLock<OpeningBook> = OnceLock::new();
+        BOOK.get_or_init(|| serde_json::from_str(EMBEDDED).expect("embedded opening book"))
+    }
+
+    // Book guess for the position, None past the second guess, off the book or while a
+    // static batch is collected
+    pub fn guess(
+        &self,
+        strategy: &str,
+        settings: &BoardSettings,
+        state: &BoardState,
+    ) -> Option<Guess> {
+        if !state.pending.is_empty() {
+            return None;
+        }
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.strategy == strategy && entry.settings.same_rules(settings))?;
+        match (state.guesses.as_slice(), state.answers.as_slice()) {
+            ([], _) => Some(entry.first.clone()),
+            ([first], [answer]) if *first == entry.first => entry
+                .second
+                .iter()
+                .find(|reply| reply.feedback == *answer)
+                .map(|reply| reply.guess.clone()),
+            _ => None,
+        }
+    }
+
+    // Runs the searching solvers on every preset and the default settings
+    pub fn generate() -> OpeningBook {
+        let mut settings = vec![BoardSettings::default()];
+        settings.extend(
+            BoardSettings::presets()
+                .into_iter()
+                .map(|(_, preset)| preset),
+        );
+        let mut breakers: Vec<Box<dyn Codebreaker>> = vec![
+            Box::new(KnuthSolver::new().use_book(false)),
+            Box::new(EntropySolver::new().use_book(false)),
+            Box::new(EntropySolver::new().consistent_only(true).use_book(false)),
+        ];
+
+        let mut entries = Vec::new();
+        for settings in &settings {
+            if !Code::supports(settings) {
+                continue;
+            }
+            for breaker in &mut breakers {
+                entries.push(Self::entry(breaker.as_mut(), settings));
+            }
+        }
+        OpeningBook { entries }
+    }
+
+    fn entry(breaker: &mut dyn Codebreaker, settings: &BoardSettings) -> BookEntry {
+        breaker.reset(settings);
+        let opening = BoardState::with_seed(settings, 0);
+        let first = breaker.next_guess(settings, &opening);
+        let first_code = Code::try_from(&first).expect("guess fits the settings");
+
+        // One code per possible answer, it stands in for the secret
+        let mut answers: BTreeMap<(u8, u8), Code> = BTreeMap::new();
+        for code in CodeSpace::new(settings).codes() {
+            let feedback = code.score(&first_code);
+            answers
+                .entry((feedback.exact, feedback.misplaced))
+                .or_insert(code);
+        }
+
+        let second = answers
+            .into_values()
+            .filter(|&code| code != first_code)
+            .map(|code| {
+                let feedback = code.score(&first_code);
+                let mut state = BoardState::with_code(settings, code.to_vec())
+                    .expect("code from the code space");
+                state.guesses.push(first.clone());
+                state.answers.push(feedback);
+                Reply {
+                    feedback,
+                    guess: breaker.next_guess(settings, &state),
+                }
+            })
+            .collect();
+
+        BookEntry {
+            strategy: breaker.name().to_string(),
+            settings: settings.clone(),
+            first,
+            second,
+        }
+    }
+
+    // One entry per line, so a regenerated book diffs well
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).expect("book serializes"))
+            .collect();
+        format!("[\n{}\n]\n", entries.join(",\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fields added to the settings have to be regenerated into the book as well
+    #[test]
+    fn embedded_book_is_written_like_the_current_settings() {
+        assert_eq!(OpeningBook::embedded().to_json(), EMBEDDED);
+    }
+}
@@ -0,0 +1,137 @@
+use super::{
+    AnswerIndex, Codebreaker, OpeningBook, SamplingSolver, candidates, guess_pool, is_candidate,
+    is_exhaustive, parallel,
+};
+use crate::mastermindlib::board::{BoardSettings, BoardState, Guess};
+use crate::mastermindlib::code::Code;
+
+// Picks the guess whose answer is expected to tell the most about the code, measured as
+// the Shannon entropy of the answers over all remaining candidates. Aims for the lowest
+// average number of guesses rather than the best worst case
+#[derive(Debug, Clone)]
+pub struct EntropySolver {
+    // Only guess codes that could still be the secret
+    pub consistent_only: bool,
+    // Upper bound of scores per guess, beyond it the candidates are sampled
+    pub max_evaluations: usize,
+    // Take the first two guesses from the embedded `OpeningBook` when it covers the settings
+    pub use_book: bool,
+    // Plays code spaces too large to enumerate
+    fallback: SamplingSolver,
+}
+
+impl Default for EntropySolver {
+    fn default() -> Self {
+        Self {
+            consistent_only: false,
+            max_evaluations: 20_000_000,
+            use_book: true,
+            fallback: SamplingSolver::with_seed(0),
+        }
+    }
+}
+
+impl EntropySolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn consistent_only(mut self, consistent_only: bool) -> Self {
+        self.consistent_only = consistent_only;
+        self
+    }
+
+    pub fn max_evaluations(mut self, max_evaluations: usize) -> Self {
+        self.max_evaluations = max_evaluations;
+        self
+    }
+
+    pub fn use_book(mut self, use_book: bool) -> Self {
+        self.use_book = use_book;
+        self
+    }
+}
+
+impl Codebreaker for EntropySolver {
+    fn name(&self) -> &str {
+        if self.consistent_only {
+            "entropy-consistent"
+        } else {
+            "entropy"
+        }
+    }
+
+    fn next_guess(&mut self, settings: &BoardSettings, state: &BoardState) -> Guess {
+        if self.use_book
+            && let Some(guess) = OpeningBook::embedded().guess(self.name(), settings, state)
+        {
+            return guess;
+        }
+        if !is_exhaustive(settings) {
+            return self.fallback.next_guess(settings, state);
+        }
+        let candidates = candidates(settings, state);
+        if candidates.len() <= 2 {
+            let first = candidates
+                .first()
+                .expect("no code is consistent with the answers");
+            return Guess::from(*first);
+        }
+
+        let pool = guess_pool(
+            settings,
+            state,
+            &candidates,
+            self.consistent_only,
+            self.max_evaluations,
+        );
+        // Estimate the answer distribution on an even sample when there are too many candidates
+        let step = (pool.len() * candidates.len()).div_ceil(self.max_evaluations.max(1));
+        let sample: Vec<Code> = candidates.iter().step_by(step.max(1)).copied().collect();
+
+        Guess::from(max_entropy_guess(settings, &pool, &sample, &candidates))
+    }
+}
+
+fn max_entropy_guess(
+    settings: &BoardSettings,
+    pool: &[Code],
+    sample: &[Code],
+    candidates: &[Code],
+) -> Code {
+    let answers = AnswerIndex::new(settings);
+    let total = sample.len() as f64;
+
+    let entropies = parallel::chunked(pool.len(), sample.len(), |range| {
+        let mut counts = vec![0usize; answers.count()];
+        pool[range]
+            .iter()
+            .map(|guess| {
+                counts.fill(0);
+                for code in sample {
+                    counts[answers.of(code, guess)] += 1;
+                }
+                counts
+                    .iter()
+                    .filter(|&&count| count > 0)
+                    .map(|&count| {
+                        let p = count as f64 / total;
+                        -p * p.log2()
+                    })
+                    .sum::<f64>()
+            })
+            .collect()
+    });
+
+    let mut best = (f64::NEG_INFINITY, pool[0]);
+    for (guess, mut entropy) in pool.iter().zip(entropies) {
+        // A guess that could be the code wins ties, it has a chance of ending the game
+        if is_candidate(candidates, guess) {
+            entropy += 1e-9;
+        }
+        if entropy > best.0 {
+            best = (entropy, *guess);
+        }
+    }
+    best.1
+}
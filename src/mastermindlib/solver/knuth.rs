@@ -0,0 +1,125 @@
+use super::{
+    AnswerIndex, Codebreaker, OpeningBook, SamplingSolver, candidates, guess_pool, is_candidate,
+    is_exhaustive, parallel,
+};
+use crate::mastermindlib::board::{BoardSettings, BoardState, Guess};
+use crate::mastermindlib::code::Code;
+
+// Donald Knuth's worst-case minimax strategy (1976). Every guess minimises the size of
+// the largest group of codes that could remain, on 4 pegs and 6 colors it always wins
+// within five guesses, starting with 1122
+#[derive(Debug, Clone)]
+pub struct KnuthSolver {
+    // Upper bound of scores per guess. If trying every code as a guess would exceed it,
+    // only the remaining candidates are tried
+    pub max_evaluations: usize,
+    // Take the first two guesses from the embedded `OpeningBook` when it covers the settings
+    pub use_book: bool,
+    // Plays code spaces too large to enumerate
+    fallback: SamplingSolver,
+}
+
+impl Default for KnuthSolver {
+    fn default() -> Self {
+        Self {
+            max_evaluations: 50_000_000,
+            use_book: true,
+            fallback: SamplingSolver::with_seed(0),
+        }
+    }
+}
+
+impl KnuthSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_evaluations(mut self, max_evaluations: usize) -> Self {
+        self.max_evaluations = max_evaluations;
+        self
+    }
+
+    pub fn use_book(mut self, use_book: bool) -> Self {
+        self.use_book = use_book;
+        self
+    }
+}
+
+impl Codebreaker for KnuthSolver {
+    fn name(&self) -> &str {
+        "knuth"
+    }
+
+    fn next_guess(&mut self, settings: &BoardSettings, state: &BoardState) -> Guess {
+        if self.use_book
+            && let Some(guess) = OpeningBook::embedded().guess(self.name(), settings, state)
+        {
+            return guess;
+        }
+        if !is_exhaustive(settings) {
+            return self.fallback.next_guess(settings, state);
+        }
+        let candidates = candidates(settings, state);
+        if candidates.len() <= 2 {
+            let first = candidates
+                .first()
+                .expect("no code is consistent with the answers");
+            return Guess::from(*first);
+        }
+
+        let pool = guess_pool(settings, state, &candidates, false, self.max_evaluations);
+        Guess::from(minimax_guess(settings, &pool, &candidates))
+    }
+}
+
+// Guess with the smallest worst case partition, ties prefer guesses that could still win
+pub(crate) fn minimax_guess(settings: &BoardSettings, pool: &[Code], candidates: &[Code]) -> Code {
+    let answers = AnswerIndex::new(settings);
+
+    let worst_cases = parallel::chunked(pool.len(), candidates.len(), |range| {
+        let mut counts = vec![0usize; answers.count()];
+        pool[range]
+            .iter()
+            .map(|guess| {
+                counts.fill(0);
+                for code in candidates {
+                    counts[answers.of(code, guess)] += 1;
+                }
+                counts.iter().copied().max().unwrap_or(0)
+            })
+            .collect()
+    });
+
+    pool.iter()
+        .zip(worst_cases)
+        .min_by_key(|&(guess, worst)| (worst, !is_candidate(candidates, guess)))
+        .map(|(guess, _)| *guess)
+        .expect("no guess to choose from")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mastermindlib::board::{MastermindBoard, Phase};
+    use crate::mastermindlib::codespace::CodeSpace;
+    use crate::mastermindlib::solver::play;
+
+    // Every one of the 1296 codes, slow without optimizations
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)]
+    fn wins_classic_mastermind_within_five_guesses() {
+        let settings = BoardSettings::default();
+        for use_book in [true, false] {
+            let mut solver = KnuthSolver::new().use_book(use_book);
+            for code in CodeSpace::new(&settings).iter() {
+                let mut board = MastermindBoard::with_code(settings.clone(), code).unwrap();
+                let phase = play(&mut solver, &mut board).unwrap();
+                assert!(
+                    matches!(phase, Phase::Won { tries } if tries <= 5),
+                    "{phase:?} against {:?}",
+                    board.state.code
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use super::board::{BoardSettings, BoardState, Guess, GuessError, MastermindBoard, Phase};
+use super::code::Code;
+use super::codespace::CodeSpace;
+use super::feedback::{Feedback, FeedbackMode};
+
+pub mod batch;
+pub mod book;
+pub mod entropy;
+pub mod knuth;
+pub mod optimal;
+pub(crate) mod parallel;
+pub mod sampling;
+pub mod simple;
+
+pub use batch::{Batch, BatchSolver};
+pub use book::OpeningBook;
+pub use entropy::EntropySolver;
+pub use knuth::KnuthSolver;
+pub use optimal::{DecisionTree, Objective, OptimalSearch, OptimalSolver};
+pub use sampling::SamplingSolver;
+pub use simple::SimpleSolver;
+
+// A strategy that plays the codebreaker side of a game
+pub trait Codebreaker {
+    fn name(&self) -> &str;
+
+    // Called before a game is played, strategies that keep state should clear it here
+    fn reset(&mut self, _settings: &BoardSettings) {}
+
+    // Proposes the next guess from everything recorded on the board so far
+    fn next_guess(&mut self, settings: &BoardSettings, state: &BoardState) -> Guess;
+
+    // Called with the answer to every guess made through `play`
+    fn observe(&mut self, _guess: &Guess, _feedback: Feedback) {}
+}
+
+// Every built-in strategy, the sampling solver uses a fixed seed so runs are reproducible
+pub fn strategies() -> Vec<Box<dyn Codebreaker>> {
+    vec![
+        Box::new(SimpleSolver),
+        Box::new(KnuthSolver::new()),
+        Box::new(EntropySolver::new()),
+        Box::new(EntropySolver::new().consistent_only(true)),
+        Box::new(SamplingSolver::with_seed(0)),
+    ]
+}
+
+// Lets the codebreaker guess until the game is over, continues games that already started.
+// The guesses of a static batch come from `BatchSolver`, the others adapt to answers
+pub fn play<B: Codebreaker + ?Sized>(
+    breaker: &mut B,
+    board: &mut MastermindBoard,
+) -> Result<Phase, GuessError> {
+    breaker.reset(&board.settings);
+    let mut batch = BatchSolver::new();
+    while !board.is_over() {
+        let guess = if board.is_collecting_batch() {
+            batch.next_guess(&board.settings, &board.state)
+        } else {
+            breaker.next_guess(&board.settings, &board.state)
+        };
+        let answered = board.state.answers.len();
+        board.try_guess(&guess)?;
+        // A static batch is answered all at once, when its last guess is made
+        for turn in answered..board.state.answers.len() {
+            breaker.observe(&board.state.guesses[turn], board.state.answers[turn]);
+        }
+    }
+    Ok(board.phase())
+}
+
+// Dense index of a feedback, lets solvers count partitions in a flat array
+pub(crate) fn feedback_index(feedback: Feedback, code_length: usize) -> usize {
+    feedback.exact as usize * (code_length + 1) + feedback.misplaced as usize
+}
+
+pub(crate) fn feedback_count(code_length: usize) -> usize {
+    (code_length + 1) * (code_length + 1)
+}
+
+// Largest code space the exhaustive solvers enumerate, e.g. 6 pegs with 8 colors. Beyond
+// it building the candidate list alone takes longer than a guess should
+pub const MAX_EXHAUSTIVE_CODES: u128 = 1 << 18;
+
+// Positional answers of longer codes don't fit a flat array of counts
+const MAX_ANSWER_INDICES: usize = 3usize.pow(10);
+
+// Dense index of the answer a code gives to a guess under the settings' feedback mode
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AnswerIndex {
+    mode: FeedbackMode,
+    length: usize,
+}
+
+impl AnswerIndex {
+    pub(crate) fn new(settings: &BoardSettings) -> Self {
+        Self {
+            mode: settings.feedback,
+            length: settings.code_length as usize,
+        }
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        match self.mode {
+            FeedbackMode::Counts => feedback_count(self.length),
+            FeedbackMode::Positional => 3usize.saturating_pow(self.length as u32),
+            FeedbackMode::ExactOnly => self.length + 1,
+        }
+    }
+
+    pub(crate) fn of(&self, code: &Code, guess: &Code) -> usize {
+        match self.mode {
+            FeedbackMode::Counts => feedback_index(code.score(guess), self.length),
+            FeedbackMode::Positional => code.verdict_index(guess),
+            FeedbackMode::ExactOnly => code.exact_matches(guess) as usize,
+        }
+    }
+
+    // Index of the answer recorded for the guess at `turn`
+    pub(crate) fn recorded(&self, state: &BoardState, turn: usize) -> usize {
+        match self.mode {
+            FeedbackMode::Counts => feedback_index(state.answers[turn], self.length),
+            FeedbackMode::Positional => state.verdicts[turn]
+                .iter()
+                .fold(0, |index, &verdict| index * 3 + verdict as usize),
+            FeedbackMode::ExactOnly => state.answers[turn].exact as usize,
+        }
+    }
+}
+
+// Whether the exhaustive solvers can handle the settings, they fall back to
+// `SamplingSolver` otherwise
+pub(crate) fn is_exhaustive(settings: &BoardSettings) -> bool {
+    Code::supports(settings)
+        && AnswerIndex::new(settings).count() <= MAX_ANSWER_INDICES
+        && settings.code_space_size() <= MAX_EXHAUSTIVE_CODES
+}
+
+// Same as `BoardState::consistent_candidates` on packed codes, needs `Code::supports`
+pub(crate) fn candidates(settings: &BoardSettings, state: &BoardState) -> Vec<Code> {
+    let lies = settings.lies as usize;
+    let answers = AnswerIndex::new(settings);
+    let history: Vec<(Code, usize)> = state
+        .guesses
+        .iter()
+        .enumerate()
+        .map(|(turn, guess)| {
+            (
+                Code::try_from(guess).expect("guess fits the settings"),
+                answers.recorded(state, turn),
+            )
+        })
+        .collect();
+    let space = CodeSpace::new(settings);
+    parallel::chunked(space.len() as usize, history.len(), |ranks| {
+        ranks
+            .map(|rank| space.unrank(rank as u64))
+            .filter(|code| {
+                // Up to `lies` answers may disagree, but never the one to a guess of the code
+                !history.iter().any(|(guess, _)| guess == code)
+                    && history
+                        .iter()
+                        .filter(|(guess, answer)| answers.of(code, guess) != *answer)
+                        .nth(lies)
+                        .is_none()
+            })
+            .collect()
+    })
+}
+
+// Guesses worth scoring against `candidates`: pattern openings for the first guess, every
+// code while that stays within `max_evaluations` scores, otherwise the candidates only
+pub(crate) fn guess_pool(
+    settings: &BoardSettings,
+    state: &BoardState,
+    candidates: &[Code],
+    consistent_only: bool,
+    max_evaluations: usize,
+) -> Vec<Code> {
+    if state.guesses.is_empty() {
+        return opening_guesses(settings);
+    }
+    let evaluations = settings.code_space_size() * candidates.len() as u128;
+    if !consistent_only && evaluations <= max_evaluations as u128 {
+        return CodeSpace::new(settings).codes().collect();
+    }
+    let limit = (max_evaluations / candidates.len()).max(1);
+    candidates.iter().take(limit).copied().collect()
+}
+
+// Candidates are sorted as both they and the guess pool come from `CodeSpace`
+pub(crate) fn is_candidate(candidates: &[Code], guess: &Code) -> bool {
+    candidates.binary_search(guess).is_ok()
+}
+
+// Before the first answer all symbols and positions are interchangeable, so only the
+// pattern of a guess matters. Returns one guess per pattern, e.g. 0011 for two pairs
+pub(crate) fn opening_guesses(settings: &BoardSettings) -> Vec<Code> {
+    let length = settings.code_length as usize;
+    // Blanks may repeat when colors can't, so only the number of blanks matters
+    if let (false, Some(blank)) = (settings.allow_repeats, settings.blank_symbol()) {
+        return (0..=length)
+            .filter(|blanks| length - blanks <= settings.symbols as usize)
+            .map(|blanks| {
+                let symbols: Vec<u8> = (0..(length - blanks) as u8)
+                    .chain(std::iter::repeat_n(blank, blanks))
+                    .collect();
+                Code::new(&symbols).expect("pattern fits the settings")
+            })
+            .collect();
+    }
+    let max_part = if settings.allow_repeats { length } else { 1 };
+    let mut patterns = Vec::new();
+    partitions(length, max_part, &mut Vec::new(), &mut patterns);
+    patterns
+        .into_iter()
+        .filter(|parts| parts.len() <= settings.symbol_count())
+        .map(|parts| {
+            let symbols: Vec<u8> = parts
+                .iter()
+                .enumerate()
+                .flat_map(|(symbol, &count)| std::iter::repeat_n(symbol as u8, count))
+                .collect();
+            Code::new(&symbols).expect("pattern fits the settings")
+        })
+        .collect()
+}
+
+// All ways to write `rest` as a sum of non-increasing parts no larger than `max_part`
+fn partitions(rest: usize, max_part: usize, parts: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+    if rest == 0 {
+        out.push(parts.clone());
+        return;
+    }
+    for part in (1..=max_part.min(rest)).rev() {
+        parts.push(part);
+        partitions(rest - part, part, parts, out);
+        parts.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adaptive_solvers_win_static_games() {
+        let settings = BoardSettings::default()
+            .symbols(4)
+            .code_length(3)
+            .static_guesses(5);
+        for mut breaker in [
+            Box::new(SimpleSolver) as Box<dyn Codebreaker>,
+            Box::new(KnuthSolver::new()),
+            Box::new(EntropySolver::new()),
+        ] {
+            for code in CodeSpace::new(&settings).iter() {
+                let mut board = MastermindBoard::with_code(settings.clone(), code).unwrap();
+                let phase = play(breaker.as_mut(), &mut board).unwrap();
+                assert!(
+                    matches!(phase, Phase::Won { .. }),
+                    "{} lost against {:?}",
+                    breaker.name(),
+                    board.state.code
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn solvers_find_repeated_blanks_without_repeats() {
+        let settings = BoardSettings::default()
+            .symbols(4)
+            .code_length(3)
+            .allow_repeats(false)
+            .allow_blanks(true);
+        // Two colors can't fill four pegs, the blank has to repeat
+        let short = settings.clone().symbols(2).code_length(4);
+        for mut breaker in strategies()
+            .into_iter()
+            .chain([Box::new(OptimalSolver::new(Objective::Expected)) as Box<dyn Codebreaker>])
+        {
+            let boards = [vec![4, 4, 4], vec![0, 4, 4], vec![4, 2, 4]]
+                .map(|code| MastermindBoard::with_code(settings.clone(), code).unwrap())
+                .into_iter()
+                .chain((0..4).map(|seed| MastermindBoard::with_seed(short.clone(), seed)));
+            for mut board in boards {
+                let phase = play(breaker.as_mut(), &mut board).unwrap();
+                assert!(
+                    matches!(phase, Phase::Won { .. }),
+                    "{} lost against {:?}",
+                    breaker.name(),
+                    board.state.code
+                );
+            }
+        }
+    }
+}
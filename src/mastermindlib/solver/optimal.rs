@@ -0,0 +1,604 @@
+use super::{
+    Codebreaker, KnuthSolver, candidates, feedback_count, feedback_index, opening_guesses,
+};
+use crate::mastermindlib::board::{BoardSettings, BoardState, Guess};
+use crate::mastermindlib::code::Code;
+use crate::mastermindlib::codespace::CodeSpace;
+use crate::mastermindlib::feedback::{Feedback, FeedbackMode};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Objective {
+    // Fewest guesses on average over all codes
+    #[default]
+    Expected,
+    // Fewest guesses for the hardest code, ties are broken by the average
+    WorstCase,
+}
+
+// A complete strategy: play `guess`, then continue with the branch of the answer. The
+// branch for a fully correct answer is left out, a node without branches has one code left
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DecisionTree {
+    pub guess: Guess,
+    // Codes still possible when this guess is played
+    pub candidates: u64,
+    pub branches: Vec<Branch>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Branch {
+    pub feedback: Feedback,
+    pub next: DecisionTree,
+}
+
+impl DecisionTree {
+    // The node reached by playing `guesses` with the given answers, None once the
+    // guesses leave the tree
+    pub fn follow(&self, guesses: &[Guess], answers: &[Feedback]) -> Option<&DecisionTree> {
+        let mut node = self;
+        for (guess, answer) in guesses.iter().zip(answers) {
+            if *guess != node.guess {
+                return None;
+            }
+            node = &node
+                .branches
+                .iter()
+                .find(|branch| branch.feedback == *answer)?
+                .next;
+        }
+        Some(node)
+    }
+
+    // Whether the guess of this node can be the code
+    pub fn guess_can_win(&self) -> bool {
+        let below: u64 = self.branches.iter().map(|b| b.next.candidates).sum();
+        self.candidates > below
+    }
+
+    // Guesses needed for the hardest code
+    pub fn worst_case(&self) -> usize {
+        1 + self
+            .branches
+            .iter()
+            .map(|branch| branch.next.worst_case())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Guesses summed over every code, divide by `candidates` for the average
+    pub fn total_guesses(&self) -> u64 {
+        self.candidates
+            + self
+                .branches
+                .iter()
+                .map(|branch| branch.next.total_guesses())
+                .sum::<u64>()
+    }
+
+    pub fn average(&self) -> f64 {
+        self.total_guesses() as f64 / self.candidates.max(1) as f64
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("tree serializes")
+    }
+
+    // Graphviz digraph, nodes show the guess and the number of codes left
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph strategy {\n    node [shape=box];\n");
+        let mut next_id = 0;
+        self.write_dot(&mut out, &mut next_id);
+        out.push_str("}\n");
+        out
+    }
+
+    fn write_dot(&self, out: &mut String, next_id: &mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+        let guess: String = self
+            .guess
+            .0
+            .iter()
+            .map(|&symbol| char::from_digit(symbol as u32, 36).unwrap_or('?'))
+            .collect();
+        let _ = writeln!(
+            out,
+            "    n{id} [label=\"{guess}\\n{} left\"];",
+            self.candidates
+        );
+        for branch in &self.branches {
+            let child = branch.next.write_dot(out, next_id);
+            let _ = writeln!(
+                out,
+                "    n{id} -> n{child} [label=\"{}B{}W\"];",
+                branch.feedback.exact, branch.feedback.misplaced
+            );
+        }
+        id
+    }
+}
+
+// Exhaustive branch and bound search for a provably optimal strategy. Only feasible for
+// small settings, up to 4 pegs with 6 colors or 5 pegs with 5 colors
+#[derive(Debug, Clone)]
+pub struct OptimalSearch {
+    pub objective: Objective,
+    // Larger code spaces are refused
+    pub max_codes: u128,
+}
+
+impl Default for OptimalSearch {
+    fn default() -> Self {
+        Self {
+            objective: Objective::Expected,
+            max_codes: 4096,
+        }
+    }
+}
+
+impl OptimalSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    pub fn max_codes(mut self, max_codes: u128) -> Self {
+        self.max_codes = max_codes;
+        self
+    }
+
+    // Optimal strategy from the position in `state`, None when the code space is too
+    // large or no code fits the answers. Trees branch on true, counted feedback only
+    pub fn tree(&self, settings: &BoardSettings, state: &BoardState) -> Option<DecisionTree> {
+        if !Code::supports(settings)
+            || settings.code_space_size() > self.max_codes
+            || settings.feedback != FeedbackMode::Counts
+            || settings.lies > 0
+        {
+            return None;
+        }
+        let candidates = candidates(settings, state);
+        if candidates.is_empty() {
+            return None;
+        }
+        let used = state
+            .guesses
+            .iter()
+            .flat_map(|guess| &guess.0)
+            .fold(0u32, |used, &symbol| used | 1 << symbol);
+
+        let mut search = Search::new(settings);
+        let depth = match self.objective {
+            Objective::Expected => u8::MAX,
+            // Iterative deepening, the first depth with a strategy is the optimal worst case
+            Objective::WorstCase => (search.lower_bound(candidates.len()).1..=u8::MAX)
+                .find(|&depth| search.solve(&candidates, used, depth, u64::MAX).is_some())?,
+        };
+        search.solve(&candidates, used, depth, u64::MAX)?;
+        Some(search.build(&candidates, depth))
+    }
+}
+
+enum Entry {
+    Exact(u64, Code),
+    // No strategy with fewer total guesses exists
+    AtLeast(u64),
+}
+
+struct Search {
+    length: usize,
+    space: Vec<Code>,
+    openings: Vec<Code>,
+    // Symbols that aren't interchangeable with the others, the blank when only it may repeat
+    fixed: u32,
+    // Answers other than a win a guess can get, bounds how fast codes can be told apart
+    outcomes: u64,
+    memo: HashMap<(Vec<Code>, u8), Entry>,
+}
+
+impl Search {
+    fn new(settings: &BoardSettings) -> Self {
+        let length = settings.code_length as usize;
+        Self {
+            length,
+            space: CodeSpace::new(settings).codes().collect(),
+            openings: opening_guesses(settings),
+            fixed: settings
+                .blank_symbol()
+                .filter(|_| !settings.allow_repeats)
+                .map_or(0, |blank| 1 << blank),
+            outcomes: ((length + 1) * (length + 2) / 2).saturating_sub(2).max(1) as u64,
+            memo: HashMap::new(),
+        }
+    }
+
+    // Least total guesses and depth for `n` codes, as if every guess were a candidate
+    // and every answer split the rest perfectly
+    fn lower_bound(&self, n: usize) -> (u64, u8) {
+        let mut remaining = n as u64;
+        let mut total = 0;
+        let mut depth = 0;
+        let mut capacity = 1u64;
+        while remaining > 0 {
+            depth += 1;
+            let solved = capacity.min(remaining);
+            total += solved * depth as u64;
+            remaining -= solved;
+            capacity = capacity.saturating_mul(self.outcomes);
+        }
+        (total, depth)
+    }
+
+    // Least total guesses for `candidates` within `depth` guesses, if below `bound`
+    fn solve(&mut self, candidates: &[Code], used: u32, depth: u8, bound: u64) -> Option<u64> {
+        let n = candidates.len();
+        let (lower, min_depth) = self.lower_bound(n);
+        if min_depth > depth || lower >= bound {
+            return None;
+        }
+        if n <= 2 {
+            return Some(lower);
+        }
+
+        let key = (candidates.to_vec(), depth);
+        match self.memo.get(&key) {
+            Some(&Entry::Exact(total, _)) => return (total < bound).then_some(total),
+            Some(&Entry::AtLeast(total)) if total >= bound => return None,
+            _ => {}
+        }
+
+        let mut best = None;
+        let mut limit = bound;
+        for (guess_lower, guess) in self.options(candidates, used) {
+            if guess_lower >= limit {
+                break;
+            }
+            let Some(total) = self.evaluate(candidates, used, depth, &guess, limit) else {
+                continue;
+            };
+            limit = total;
+            best = Some((total, guess));
+            if total == lower {
+                break;
+            }
+        }
+
+        match best {
+            Some((total, guess)) => {
+                self.memo.insert(key, Entry::Exact(total, guess));
+                Some(total)
+            }
+            None => {
+                self.memo.insert(key, Entry::AtLeast(bound));
+                None
+            }
+        }
+    }
+
+    // Total guesses when `guess` is played next, if below `bound`
+    fn evaluate(
+        &mut self,
+        candidates: &[Code],
+        used: u32,
+        depth: u8,
+        guess: &Code,
+        bound: u64,
+    ) -> Option<u64> {
+        let classes = self.partition(candidates, guess);
+        let used = guess
+            .symbols()
+            .fold(used, |used, symbol| used | 1 << symbol);
+        let mut total = candidates.len() as u64
+            + classes
+                .iter()
+                .map(|class| self.lower_bound(class.len()).0)
+                .sum::<u64>();
+        for class in &classes {
+            let class_lower = self.lower_bound(class.len()).0;
+            let rest = total - class_lower;
+            let child = self.solve(class, used, child_depth(depth), bound.checked_sub(rest)?)?;
+            total = rest + child;
+        }
+        (total < bound).then_some(total)
+    }
+
+    // Guesses worth trying with their lower bound, best first. Unused symbols are
+    // interchangeable and guesses splitting the candidates the same way are equivalent,
+    // so only one of each is kept
+    fn options(&self, candidates: &[Code], used: u32) -> Vec<(u64, Code)> {
+        let pool = if used == 0 {
+            &self.openings
+        } else {
+            &self.space
+        };
+        let mut seen = HashSet::new();
+        let mut counts = vec![0usize; feedback_count(self.length)];
+        let mut options = Vec::new();
+        for guess in pool {
+            if !self.is_canonical(guess, used) {
+                continue;
+            }
+            let signature: Vec<u8> = candidates
+                .iter()
+                .map(|code| feedback_index(code.score(guess), self.length) as u8)
+                .collect();
+            counts.fill(0);
+            for &index in &signature {
+                counts[index as usize] += 1;
+            }
+            // A guess that can't split the candidates gains nothing
+            if counts.contains(&candidates.len()) && !signature.contains(&self.win_index()) {
+                continue;
+            }
+            if !seen.insert(signature) {
+                continue;
+            }
+            let lower = candidates.len() as u64
+                + counts
+                    .iter()
+                    .enumerate()
+                    .filter(|&(index, &count)| count > 0 && index != self.win_index() as usize)
+                    .map(|(_, &count)| self.lower_bound(count).0)
+                    .sum::<u64>();
+            options.push((lower, *guess));
+        }
+        options.sort_by_key(|&(lower, _)| lower);
+        options
+    }
+
+    // Symbols that weren't guessed yet have to appear in ascending order, starting with
+    // the lowest of them
+    fn is_canonical(&self, guess: &Code, used: u32) -> bool {
+        let mut seen = used | self.fixed;
+        for symbol in guess.symbols() {
+            if seen & 1 << symbol == 0 {
+                if symbol as u32 != (!seen).trailing_zeros() {
+                    return false;
+                }
+                seen |= 1 << symbol;
+            }
+        }
+        true
+    }
+
+    fn win_index(&self) -> u8 {
+        feedback_index(
+            Feedback {
+                exact: self.length as u8,
+                misplaced: 0,
+            },
+            self.length,
+        ) as u8
+    }
+
+    // Candidates grouped by answer to `guess` without the winning one, largest first
+    fn partition(&self, candidates: &[Code], guess: &Code) -> Vec<Vec<Code>> {
+        let mut classes = vec![Vec::new(); feedback_count(self.length)];
+        for code in candidates {
+            classes[feedback_index(code.score(guess), self.length)].push(*code);
+        }
+        classes[self.win_index() as usize].clear();
+        classes.retain(|class| !class.is_empty());
+        classes.sort_by_key(|class| std::cmp::Reverse(class.len()));
+        classes
+    }
+
+    // Reads the strategy found by `solve` back out of the memo
+    fn build(&self, candidates: &[Code], depth: u8) -> DecisionTree {
+        let guess = match candidates.len() {
+            1 | 2 => candidates[0],
+            _ => match self.memo.get(&(candidates.to_vec(), depth)) {
+                Some(&Entry::Exact(_, guess)) => guess,
+                _ => unreachable!("solved positions are in the memo"),
+            },
+        };
+        let mut branches: Vec<Branch> = self
+            .partition(candidates, &guess)
+            .into_iter()
+            .map(|class| Branch {
+                feedback: class[0].score(&guess),
+                next: self.build(&class, child_depth(depth)),
+            })
+            .collect();
+        branches.sort_by_key(|branch| feedback_index(branch.feedback, self.length));
+        DecisionTree {
+            guess: Guess::from(guess),
+            candidates: candidates.len() as u64,
+            branches,
+        }
+    }
+}
+
+// Depth left below a guess, `u8::MAX` stands for no limit
+fn child_depth(depth: u8) -> u8 {
+    if depth == u8::MAX { depth } else { depth - 1 }
+}
+
+// Guesses played before a tree starts, with their answers
+type Prefix = (Vec<Guess>, Vec<Feedback>);
+
+// Plays a precomputed optimal strategy, the tree is reused between games with the same
+// settings. Falls back to `KnuthSolver` for code spaces the search refuses
+#[derive(Debug, Clone, Default)]
+pub struct OptimalSolver {
+    pub search: OptimalSearch,
+    // Settings, guesses and answers the cached tree starts from
+    tree: Option<(BoardSettings, Prefix, DecisionTree)>,
+}
+
+impl OptimalSolver {
+    pub fn new(objective: Objective) -> Self {
+        Self {
+            search: OptimalSearch::new().objective(objective),
+            tree: None,
+        }
+    }
+
+    pub fn tree(&self) -> Option<&DecisionTree> {
+        self.tree.as_ref().map(|(_, _, tree)| tree)
+    }
+
+    fn cached_guess(&self, settings: &BoardSettings, state: &BoardState) -> Option<Guess> {
+        let (tree_settings, prefix, tree) = self.tree.as_ref()?;
+        // The tree only holds for the answers the prefix got when it was built
+        if tree_settings != settings
+            || !state.guesses.starts_with(&prefix.0)
+            || !state.answers.starts_with(&prefix.1)
+        {
+            return None;
+        }
+        let start = prefix.0.len();
+        let node = tree.follow(&state.guesses[start..], &state.answers[start..])?;
+        Some(node.guess.clone())
+    }
+}
+
+impl Codebreaker for OptimalSolver {
+    fn name(&self) -> &str {
+        match self.search.objective {
+            Objective::Expected => "optimal-expected",
+            Objective::WorstCase => "optimal-worst",
+        }
+    }
+
+    fn reset(&mut self, settings: &BoardSettings) {
+        if self
+            .tree
+            .as_ref()
+            .is_some_and(|(tree_settings, _, _)| tree_settings != settings)
+        {
+            self.tree = None;
+        }
+    }
+
+    fn next_guess(&mut self, settings: &BoardSettings, state: &BoardState) -> Guess {
+        if let Some(guess) = self.cached_guess(settings, state) {
+            return guess;
+        }
+        match self.search.tree(settings, state) {
+            Some(tree) => {
+                let guess = tree.guess.clone();
+                let prefix = (state.guesses.clone(), state.answers.clone());
+                self.tree = Some((settings.clone(), prefix, tree));
+                guess
+            }
+            None => KnuthSolver::new().next_guess(settings, state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mastermindlib::board::MastermindBoard;
+    use crate::mastermindlib::feedback::score;
+
+    fn tree(settings: &BoardSettings, objective: Objective) -> DecisionTree {
+        OptimalSearch::new()
+            .objective(objective)
+            .tree(settings, &BoardState::with_seed(settings, 0))
+            .expect("small enough to search")
+    }
+
+    fn nodes(tree: &DecisionTree) -> usize {
+        1 + tree
+            .branches
+            .iter()
+            .map(|branch| nodes(&branch.next))
+            .sum::<usize>()
+    }
+
+    #[test]
+    fn finds_optimal_strategies_for_small_games() {
+        // 206 was confirmed by a brute force over every strategy
+        let three_pegs = BoardSettings::default().symbols(4).code_length(3);
+        let expected = tree(&three_pegs, Objective::Expected);
+        assert_eq!(expected.candidates, 64);
+        assert_eq!(expected.total_guesses(), 206);
+        assert_eq!(expected.worst_case(), 4);
+
+        let four_pegs = BoardSettings::default().symbols(4).code_length(4);
+        assert_eq!(tree(&four_pegs, Objective::WorstCase).worst_case(), 4);
+    }
+
+    #[test]
+    fn following_the_tree_finds_every_code() {
+        let settings = BoardSettings::default().symbols(4).code_length(3);
+        let tree = tree(&settings, Objective::Expected);
+        let mut total = 0;
+        let mut worst = 0;
+        for code in CodeSpace::new(&settings).iter() {
+            let mut guesses = Vec::new();
+            let mut answers = Vec::new();
+            loop {
+                let node = tree
+                    .follow(&guesses, &answers)
+                    .expect("answers stay in the tree");
+                let guess = node.guess.clone();
+                answers.push(score(&code, &guess.0));
+                guesses.push(guess);
+                if guesses.last().unwrap().0 == code {
+                    break;
+                }
+            }
+            total += guesses.len() as u64;
+            worst = worst.max(guesses.len());
+        }
+        assert_eq!(total, tree.total_guesses());
+        assert_eq!(worst, tree.worst_case());
+        // A guess the tree doesn't play leaves it
+        let other = Guess(vec![3, 3, 3]);
+        assert_ne!(tree.guess, other);
+        assert!(
+            tree.follow(&[other], &[score(&[0, 0, 0], &[3, 3, 3])])
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn exports_round_trip_and_draw_every_node() {
+        let tree = tree(
+            &BoardSettings::default().symbols(4).code_length(3),
+            Objective::WorstCase,
+        );
+        let parsed: DecisionTree = serde_json::from_str(&tree.to_json()).unwrap();
+        assert_eq!(parsed, tree);
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph strategy {"));
+        let edges = dot.lines().filter(|line| line.contains("->")).count();
+        let labels = dot.lines().filter(|line| line.contains("[label=")).count();
+        assert_eq!(labels - edges, nodes(&tree));
+        assert_eq!(edges, nodes(&tree) - 1);
+    }
+
+    #[test]
+    fn reused_tree_follows_the_answers_of_each_game() {
+        // Every game opens with the same guess, the tree built after one game's answer
+        // doesn't fit the next one
+        let settings = BoardSettings::default().symbols(4).code_length(3);
+        let opening = Guess(vec![0, 0, 1]);
+        let mut solver = OptimalSolver::new(Objective::Expected);
+        for code in CodeSpace::new(&settings).iter() {
+            let mut board = MastermindBoard::with_code(settings.clone(), code).unwrap();
+            board.try_guess(&opening).unwrap();
+            // The opening was the code, there is nothing left to guess
+            if board.is_over() {
+                continue;
+            }
+            let fresh = OptimalSolver::new(Objective::Expected).next_guess(&settings, &board.state);
+            assert_eq!(
+                solver.next_guess(&settings, &board.state),
+                fresh,
+                "against {:?}",
+                board.state.code
+            );
+        }
+    }
+}
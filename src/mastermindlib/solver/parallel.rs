@@ -0,0 +1,45 @@
+use std::ops::Range;
+
+// Splits `0..len` into contiguous chunks, runs `f` on each and concatenates the results in
+// order, so the outcome is the same as `f(0..len)`. Uses scoped threads on native targets,
+// wasm32 runs everything on the calling thread. `work_per_item` is a rough cost estimate
+pub(crate) fn chunked<R, F>(len: usize, work_per_item: usize, f: F) -> Vec<R>
+where
+    R: Send,
+    F: Fn(Range<usize>) -> Vec<R> + Sync,
+{
+    let threads = thread_count(len.saturating_mul(work_per_item.max(1)));
+    if threads <= 1 {
+        return f(0..len);
+    }
+
+    let chunk = len.div_ceil(threads);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..len)
+            .step_by(chunk)
+            .map(|start| {
+                let f = &f;
+                scope.spawn(move || f(start..(start + chunk).min(len)))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("solver thread panicked"))
+            .collect()
+    })
+}
+
+// Below this many scores per thread spawning costs more than it saves
+#[cfg(not(target_arch = "wasm32"))]
+const MIN_WORK_PER_THREAD: usize = 200_000;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn thread_count(work: usize) -> usize {
+    let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+    available.min(work / MIN_WORK_PER_THREAD).max(1)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn thread_count(_work: usize) -> usize {
+    1
+}
@@ -0,0 +1,286 @@
+use super::Codebreaker;
+use crate::mastermindlib::board::{BoardSettings, BoardState, Guess};
+use crate::mastermindlib::codespace::CodeSpace;
+use crate::mastermindlib::feedback::{Feedback, FeedbackMode, Verdict, score, verdicts};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeSet, HashSet};
+use std::time::Duration;
+
+// Generations without a new consistent code before the search settles for what it found
+const MAX_STALLED_GENERATIONS: usize = 20;
+
+// Draws of a first guess that isn't already part of the static batch
+const MAX_REDRAWS: usize = 64;
+
+// Searches for codes consistent with the answers with a genetic algorithm instead of
+// enumerating the code space, so it keeps working on settings with billions of codes.
+// When the budget runs out before a consistent code turns up it plays the closest one
+#[derive(Debug, Clone)]
+pub struct SamplingSolver {
+    // Codes evaluated per guess
+    pub max_iterations: usize,
+    // Wall clock limit per guess, ignored on wasm32 which has no clock
+    pub time_limit: Option<Duration>,
+    pub population: usize,
+    // Stop searching once this many consistent codes were found
+    pub max_eligible: usize,
+    rng: StdRng,
+}
+
+impl Default for SamplingSolver {
+    fn default() -> Self {
+        Self::with_seed(rand::rng().random())
+    }
+}
+
+impl SamplingSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            max_iterations: 200_000,
+            time_limit: None,
+            population: 150,
+            max_eligible: 60,
+            // Salted, a board made from the same seed would otherwise have the first guess
+            // as its code
+            rng: StdRng::seed_from_u64(seed ^ 0x5eed_5a17),
+        }
+    }
+
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    fn random_code(&mut self, settings: &BoardSettings) -> Vec<u8> {
+        CodeSpace::new(settings).random_code(&mut self.rng)
+    }
+
+    // Index of the fitter of two random members, lower distance is fitter
+    fn tournament(&mut self, population: &[(u32, Vec<u8>)]) -> usize {
+        let a = self.rng.random_range(0..population.len());
+        let b = self.rng.random_range(0..population.len());
+        if population[a].0 <= population[b].0 {
+            a
+        } else {
+            b
+        }
+    }
+
+    // Builds a child from two parents with crossover, mutation, swaps and inversion
+    fn offspring(&mut self, settings: &BoardSettings, a: &[u8], b: &[u8]) -> Vec<u8> {
+        let length = a.len();
+        let mut child: Vec<u8> = if self.rng.random_bool(0.5) {
+            let cut = self.rng.random_range(0..=length);
+            a[..cut].iter().chain(&b[cut..]).copied().collect()
+        } else {
+            a.iter()
+                .zip(b)
+                .map(|(&x, &y)| if self.rng.random_bool(0.5) { x } else { y })
+                .collect()
+        };
+
+        if self.rng.random_bool(0.3) {
+            let position = self.rng.random_range(0..length);
+            child[position] = self.rng.random_range(0..settings.symbol_count()) as u8;
+        }
+        if length > 1 && self.rng.random_bool(0.3) {
+            let i = self.rng.random_range(0..length);
+            let j = self.rng.random_range(0..length);
+            child.swap(i, j);
+        }
+        if length > 1 && self.rng.random_bool(0.1) {
+            let i = self.rng.random_range(0..length);
+            let j = self.rng.random_range(i..length);
+            child[i..=j].reverse();
+        }
+
+        if !settings.allow_repeats {
+            self.repair(settings, &mut child);
+        }
+        child
+    }
+
+    // Replaces repeated symbols with random ones the code doesn't use yet, blanks may repeat
+    // so they are always free
+    fn repair(&mut self, settings: &BoardSettings, code: &mut [u8]) {
+        let blank = settings.blank_symbol();
+        for i in 0..code.len() {
+            if Some(code[i]) != blank && code[..i].contains(&code[i]) {
+                let free: Vec<u8> = (0..settings.symbol_count())
+                    .map(|symbol| symbol as u8)
+                    .filter(|&symbol| Some(symbol) == blank || !code.contains(&symbol))
+                    .collect();
+                code[i] = free[self.rng.random_range(0..free.len())];
+            }
+        }
+    }
+}
+
+// How far a code is from being consistent, zero means it could be the secret. The
+// `settings.lies` worst answers are ignored, they may have been the false ones
+fn distance(settings: &BoardSettings, state: &BoardState, code: &[u8]) -> u32 {
+    let mut distances: Vec<u32> = match settings.feedback {
+        FeedbackMode::Counts => state
+            .guesses
+            .iter()
+            .zip(&state.answers)
+            .map(|(guess, answer)| {
+                let feedback = score(code, &guess.0);
+                feedback.exact.abs_diff(answer.exact) as u32
+                    + feedback.misplaced.abs_diff(answer.misplaced) as u32
+            })
+            .collect(),
+        FeedbackMode::ExactOnly => state
+            .guesses
+            .iter()
+            .zip(&state.answers)
+            .map(|(guess, answer)| score(code, &guess.0).exact.abs_diff(answer.exact) as u32)
+            .collect(),
+        FeedbackMode::Positional => state
+            .guesses
+            .iter()
+            .zip(&state.verdicts)
+            .map(|(guess, recorded)| {
+                let verdicts = verdicts(code, &guess.0);
+                verdicts
+                    .iter()
+                    .zip(recorded)
+                    .filter(|(a, b)| a != b)
+                    .count() as u32
+            })
+            .collect(),
+    };
+    distances.sort_unstable();
+    distances.iter().rev().skip(settings.lies as usize).sum()
+}
+
+// What the codemaker would answer, verdicts are left empty unless the mode has them
+fn answer(settings: &BoardSettings, code: &[u8], guess: &[u8]) -> (Feedback, Vec<Verdict>) {
+    match settings.feedback {
+        FeedbackMode::Counts => (score(code, guess), Vec::new()),
+        FeedbackMode::Positional => (score(code, guess), verdicts(code, guess)),
+        FeedbackMode::ExactOnly => {
+            let exact = score(code, guess).exact;
+            (
+                Feedback {
+                    exact,
+                    misplaced: 0,
+                },
+                Vec::new(),
+            )
+        }
+    }
+}
+
+impl Codebreaker for SamplingSolver {
+    fn name(&self) -> &str {
+        "sampling"
+    }
+
+    fn next_guess(&mut self, settings: &BoardSettings, state: &BoardState) -> Guess {
+        // Nothing is answered yet, a static batch only needs its guesses to differ
+        if state.guesses.is_empty() {
+            let mut code = self.random_code(settings);
+            for _ in 0..MAX_REDRAWS {
+                if !state.pending.iter().any(|guess| guess.0 == code) {
+                    break;
+                }
+                code = self.random_code(settings);
+            }
+            return Guess(code);
+        }
+
+        let deadline = Deadline::new(self.time_limit);
+
+        let mut population: Vec<(u32, Vec<u8>)> = (0..self.population.max(2))
+            .map(|_| {
+                let code = self.random_code(settings);
+                (distance(settings, state, &code), code)
+            })
+            .collect();
+        // Ordered so the pick below only depends on the seed
+        let mut eligible: BTreeSet<Vec<u8>> = BTreeSet::new();
+        let mut best = population[0].clone();
+        let mut iterations = population.len();
+        // Small code spaces may not have `max_eligible` consistent codes left
+        let mut stalled = 0;
+
+        while iterations < self.max_iterations && eligible.len() < self.max_eligible {
+            if deadline.passed() || (!eligible.is_empty() && stalled >= MAX_STALLED_GENERATIONS) {
+                break;
+            }
+            let found = eligible.len();
+            let mut next = Vec::with_capacity(population.len());
+            for _ in 0..population.len() {
+                let a = self.tournament(&population);
+                let b = self.tournament(&population);
+                let child = self.offspring(settings, &population[a].1, &population[b].1);
+                let fitness = distance(settings, state, &child);
+                if fitness == 0 && !state.guesses.iter().any(|guess| guess.0 == child) {
+                    eligible.insert(child.clone());
+                }
+                if fitness < best.0 {
+                    best = (fitness, child.clone());
+                }
+                next.push((fitness, child));
+            }
+            iterations += next.len();
+            population = next;
+            stalled = if eligible.len() > found {
+                0
+            } else {
+                stalled + 1
+            };
+        }
+
+        if eligible.is_empty() {
+            return Guess(best.1);
+        }
+
+        // Play the eligible code that splits the other eligible codes into the most groups
+        let eligible: Vec<Vec<u8>> = eligible.into_iter().collect();
+        let guess = eligible
+            .iter()
+            .max_by_key(|guess| {
+                eligible
+                    .iter()
+                    .map(|code| answer(settings, code, guess))
+                    .collect::<HashSet<_>>()
+                    .len()
+            })
+            .expect("eligible is not empty");
+        Guess(guess.clone())
+    }
+}
+
+// wasm32 has no clock, the deadline never passes there
+struct Deadline {
+    #[cfg(not(target_arch = "wasm32"))]
+    end: Option<std::time::Instant>,
+}
+
+impl Deadline {
+    fn new(_limit: Option<Duration>) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            end: _limit.map(|limit| std::time::Instant::now() + limit),
+        }
+    }
+
+    fn passed(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.end.is_some_and(|end| std::time::Instant::now() >= end);
+        #[cfg(target_arch = "wasm32")]
+        false
+    }
+}
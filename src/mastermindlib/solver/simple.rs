@@ -0,0 +1,19 @@
+use super::Codebreaker;
+use crate::mastermindlib::board::{BoardSettings, BoardState, Guess};
+
+// Always guesses the first code (in lexicographic order) that is still possible
+#[derive(Debug, Default, Clone)]
+pub struct SimpleSolver;
+
+impl Codebreaker for SimpleSolver {
+    fn name(&self) -> &str {
+        "simple"
+    }
+
+    fn next_guess(&mut self, settings: &BoardSettings, state: &BoardState) -> Guess {
+        state
+            .consistent_candidates(settings)
+            .next()
+            .expect("no code is consistent with the answers")
+    }
+}
@@ -1,6 +1,11 @@
 use egui::Widget;
-use mastermind::mastermindlib::board::{BoardSettings, GameState, Guess, MastermindBoard};
-use rgb::RGB8;
+use mastermind::mastermindlib::board::{
+    Alphabet, BoardSettings, GameState, Guess, GuessError, MastermindBoard, Phase,
+};
+use mastermind::mastermindlib::feedback::FeedbackMode;
+use mastermind::mastermindlib::hint::{Hint, HintKind};
+use mastermind::mastermindlib::liar::Liar;
+use mastermind::mastermindlib::palette::Palette;
 
 use crate::mastermindwidget::{GuessState, MastermindWidget};
 
@@ -14,7 +19,6 @@ struct Preset {
 #[serde(default)]
 pub struct App {
     settings: BoardSettings,
-    color_count: i16,
     selected_preset: Option<Preset>,
 
     #[serde(skip)]
@@ -27,6 +31,9 @@ pub struct App {
     guess_state: GuessState,
 
     #[serde(skip)]
+    guess_error: Option<GuessError>,
+
+    // Saved with the app so an unfinished game survives restarts and reloads
     board: MastermindBoard,
 
     #[serde(skip)]
@@ -40,21 +47,31 @@ enum Page {
 
 impl Default for App {
     fn default() -> Self {
-        let mut settings = BoardSettings::default().code_length(4).max_tries(8);
-        settings.generate_colors(4);
+        let settings = BoardSettings::default()
+            .symbols(4)
+            .code_length(4)
+            .max_tries(8);
         Self {
             guess_state: GuessState::new(&settings),
-            color_count: settings.colors.len() as i16,
             settings: settings.clone(),
             board: MastermindBoard::new(settings),
             current_page: Page::Home,
             show_loss: false,
             show_win: false,
+            guess_error: None,
             selected_preset: None,
         }
     }
 }
 
+fn feedback_name(mode: FeedbackMode) -> &'static str {
+    match mode {
+        FeedbackMode::Counts => "Count pegs",
+        FeedbackMode::Positional => "Per peg (easier)",
+        FeedbackMode::ExactOnly => "Exact pegs only (harder)",
+    }
+}
+
 impl App {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let mut app: App = if let Some(storage) = cc.storage {
@@ -62,8 +79,7 @@ impl App {
         } else {
             Default::default()
         };
-        app.board = MastermindBoard::new(app.settings.clone());
-        app.guess_state = GuessState::new(&app.settings);
+        app.guess_state = GuessState::new(&app.board.settings);
 
         app
     }
@@ -74,19 +90,79 @@ impl App {
                 ui.heading("Mastermind");
                 MastermindWidget {
                     board: &self.board,
+                    palette: &Palette::for_symbols(self.board.settings.symbols),
                     guess_state: &mut self.guess_state,
                 }
                 .ui(ui);
-                if ui.button("Guess").clicked() {
-                    if let GameState::GameEnd(has_won) =
-                        self.board.guess(&Guess::from(&self.guess_state))
-                    {
-                        if (has_won) {
-                            self.show_win = true;
-                        } else {
-                            self.show_loss = true;
+                ui.label(match self.board.phase() {
+                    Phase::InProgress if self.board.is_collecting_batch() => format!(
+                        "Batch: {} more guesses before any answer",
+                        self.board.remaining_tries() - 1
+                    ),
+                    Phase::InProgress => format!("Tries left: {}", self.board.remaining_tries()),
+                    Phase::Won { tries } => format!("Solved in {tries} tries"),
+                    Phase::Lost => "Out of tries".to_string(),
+                });
+                if ui
+                    .add_enabled(!self.board.is_over(), egui::Button::new("Guess"))
+                    .clicked()
+                {
+                    match self.board.try_guess(&Guess::from(&self.guess_state)) {
+                        Ok(GameState::GameEnd(has_won)) => {
+                            if (has_won) {
+                                self.show_win = true;
+                            } else {
+                                self.show_loss = true;
+                            }
+                            self.guess_error = None;
                         }
+                        Ok(_) => self.guess_error = None,
+                        Err(err) => self.guess_error = Some(err),
+                    }
+                }
+                ui.horizontal(|ui| {
+                    for (label, kind) in [
+                        ("Suggest guess", HintKind::SuggestGuess),
+                        ("Reveal peg", HintKind::RevealPeg),
+                        ("Eliminate color", HintKind::EliminateColor),
+                        ("Count codes", HintKind::CountRemaining),
+                    ] {
+                        // Hints that can't finish on these settings are disabled
+                        if ui
+                            .add_enabled(self.board.hint_available(kind), egui::Button::new(label))
+                            .clicked()
+                        {
+                            match self.board.hint(kind) {
+                                Ok(Hint::Suggestion(guess)) => {
+                                    self.guess_state = GuessState::from(&guess);
+                                    self.guess_error = None;
+                                }
+                                Ok(_) => self.guess_error = None,
+                                Err(err) => self.guess_error = Some(err),
+                            }
+                        }
+                    }
+                });
+                // Lies are only revealed once the game is over
+                if self.board.is_over() {
+                    for turn in &self.board.state.lied {
+                        ui.label(format!("The answer to guess {} was a lie", turn + 1));
                     }
+                } else if self.board.settings.lies > 0 {
+                    ui.label(format!(
+                        "Up to {} answers may be lies",
+                        self.board.settings.lies
+                    ));
+                }
+                for record in &self.board.state.hints {
+                    ui.label(format!(
+                        "Hint after {} guesses: {}",
+                        record.turn,
+                        record.hint.message(&self.board.settings)
+                    ));
+                }
+                if let Some(err) = &self.guess_error {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
                 }
             }
             Page::Settings => {
@@ -101,49 +177,33 @@ impl App {
                 egui::ComboBox::from_label("Preset")
                     .selected_text(selected_text)
                     .show_ui(ui, |ui| {
-                        ui.selectable_value(
-                            &mut self.selected_preset,
-                            Some(Preset {
-                                name: "Easy".to_string(),
-                                settings: BoardSettings::default()
-                                    .with_n_colors(3)
-                                    .code_length(4)
-                                    .max_tries(5),
-                            }),
-                            "Easy",
-                        );
-                        ui.selectable_value(
-                            &mut self.selected_preset,
-                            Some(Preset {
-                                name: "Medium".to_string(),
-                                settings: BoardSettings::default()
-                                    .with_n_colors(5)
-                                    .code_length(6)
-                                    .max_tries(6),
-                            }),
-                            "Medium",
-                        );
-                        ui.selectable_value(
-                            &mut self.selected_preset,
-                            Some(Preset {
-                                name: "Hard".to_string(),
-                                settings: BoardSettings::default()
-                                    .with_n_colors(5)
-                                    .code_length(7)
-                                    .max_tries(7),
-                            }),
-                            "Hard",
-                        );
+                        for (name, settings) in BoardSettings::presets() {
+                            ui.selectable_value(
+                                &mut self.selected_preset,
+                                Some(Preset {
+                                    name: name.to_string(),
+                                    settings,
+                                }),
+                                name,
+                            );
+                        }
                     });
 
                 if self.selected_preset != previous && self.selected_preset.is_some() {
                     self.settings = self.selected_preset.clone().unwrap().settings;
                 }
 
+                // Without repeats every peg needs its own color, unless blanks fill the rest
+                let max_code_length = if self.settings.allow_repeats || self.settings.allow_blanks {
+                    10
+                } else {
+                    self.settings.symbol_count().min(10) as u8
+                };
+
                 let mut some_changed = false;
                 some_changed |= ui
                     .add(
-                        egui::Slider::new(&mut self.settings.code_length, 1..=10)
+                        egui::Slider::new(&mut self.settings.code_length, 1..=max_code_length)
                             .text("Code Length"),
                     )
                     .changed();
@@ -152,11 +212,81 @@ impl App {
                     .add(egui::Slider::new(&mut self.settings.max_tries, 1..=12).text("Max Tries"))
                     .changed();
 
-                let response =
-                    ui.add(egui::Slider::new(&mut self.color_count, 2..=9).text("Color Count"));
-                some_changed |= response.changed();
-                if response.changed() {
-                    self.settings.generate_colors(self.color_count);
+                // Only clamped when dragged, so the 10 digits of Bulls and Cows stay as they are
+                some_changed |= ui
+                    .add(
+                        egui::Slider::new(&mut self.settings.symbols, 2..=9)
+                            .clamping(egui::SliderClamping::Edits)
+                            .text("Color Count"),
+                    )
+                    .changed();
+
+                // 0 plays a normal game, otherwise the batch is answered only once it's complete
+                some_changed |= ui
+                    .add(
+                        egui::Slider::new(&mut self.settings.static_guesses, 0..=11)
+                            .text("Static batch size"),
+                    )
+                    .changed();
+
+                some_changed |= ui
+                    .add(egui::Slider::new(&mut self.settings.lies, 0..=3).text("Lies per game"))
+                    .changed();
+                let mut adversarial = self.settings.liar == Liar::Adversarial;
+                if self.settings.lies > 0
+                    && ui
+                        .checkbox(&mut adversarial, "Lie where it hurts most")
+                        .changed()
+                {
+                    self.settings.liar = if adversarial {
+                        Liar::Adversarial
+                    } else {
+                        Liar::Random
+                    };
+                    some_changed = true;
+                }
+
+                some_changed |= ui
+                    .checkbox(&mut self.settings.allow_repeats, "Allow repeated colors")
+                    .changed();
+                some_changed |= ui
+                    .checkbox(&mut self.settings.allow_blanks, "Allow blank pegs")
+                    .changed();
+                egui::ComboBox::from_label("Feedback")
+                    .selected_text(feedback_name(self.settings.feedback))
+                    .show_ui(ui, |ui| {
+                        for mode in [
+                            FeedbackMode::Counts,
+                            FeedbackMode::Positional,
+                            FeedbackMode::ExactOnly,
+                        ] {
+                            some_changed |= ui
+                                .selectable_value(
+                                    &mut self.settings.feedback,
+                                    mode,
+                                    feedback_name(mode),
+                                )
+                                .changed();
+                        }
+                    });
+
+                let mut digits = self.settings.alphabet == Alphabet::Digits;
+                if ui
+                    .checkbox(&mut digits, "Show digits instead of colors")
+                    .changed()
+                {
+                    self.settings.alphabet = if digits {
+                        Alphabet::Digits
+                    } else {
+                        Alphabet::Colors
+                    };
+                    some_changed = true;
+                }
+                if !self.settings.allow_repeats && !self.settings.allow_blanks {
+                    self.settings.code_length = self
+                        .settings
+                        .code_length
+                        .min(self.settings.symbol_count() as u8);
                 }
 
                 if some_changed {
@@ -204,6 +334,7 @@ impl eframe::App for App {
                     if ui.button("Reset").clicked() {
                         self.board = MastermindBoard::new(self.settings.clone());
                         self.guess_state = GuessState::new(&self.settings);
+                        self.guess_error = None;
                     }
                 });
             });
@@ -222,6 +353,11 @@ impl eframe::App for App {
         if self.show_win {
             egui::Modal::new("win_modal".into()).show(ctx, |ui| {
                 ui.label("You won");
+                if let Some(outcome) = self.board.outcome()
+                    && outcome.hints > 0
+                {
+                    ui.label(format!("Hints used: {}", outcome.hints));
+                }
                 ui.horizontal(|ui| {
                     if ui.button("OK").clicked() {
                         self.show_win = false;
@@ -233,7 +369,10 @@ impl eframe::App for App {
         if self.show_loss {
             egui::Modal::new("loss_modal".into()).show(ctx, |ui| {
                 ui.label("You're out of moves");
-                ui.label(format!("The solution was {}", self.board.state.code.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", ")));
+                ui.label(format!(
+                    "The solution was {}",
+                    self.board.settings.format_code(&self.board.state.code)
+                ));
                 ui.horizontal(|ui| {
                     if ui.button("OK").clicked() {
                         self.show_loss = false;
@@ -1,5 +1,7 @@
-use egui::{Color32, Grid, Painter, Pos2, Rect, Response, Sense, Ui, Widget};
-use mastermind::mastermindlib::board::{BoardSettings, GameState, Guess, MastermindBoard};
+use egui::{Color32, Painter, Rect, Response, Sense, Ui, Widget};
+use mastermind::mastermindlib::board::{Alphabet, BoardSettings, Guess, MastermindBoard};
+use mastermind::mastermindlib::feedback::{FeedbackMode, Verdict};
+use mastermind::mastermindlib::palette::Palette;
 use rgb::RGB8;
 
 trait egui_color_convertable {
@@ -21,6 +23,12 @@ impl From<&GuessState> for Guess {
     }
 }
 
+impl From<&Guess> for GuessState {
+    fn from(guess: &Guess) -> GuessState {
+        GuessState(guess.0.iter().map(|&x| x as i16).collect())
+    }
+}
+
 impl GuessState {
     pub fn new(settings: &BoardSettings) -> Self {
         Self {
@@ -31,9 +39,54 @@ impl GuessState {
 
 pub struct MastermindWidget<'a> {
     pub board: &'a MastermindBoard,
+    pub palette: &'a Palette,
     pub guess_state: &'a mut GuessState,
 }
 
+// Wordle colors
+fn verdict_color(verdict: Verdict) -> Color32 {
+    match verdict {
+        Verdict::Correct => Color32::from_rgb(83, 141, 78),
+        Verdict::Present => Color32::from_rgb(201, 180, 88),
+        Verdict::Absent => Color32::from_rgb(90, 90, 92),
+    }
+}
+
+impl MastermindWidget<'_> {
+    fn paint_peg(&self, painter: &Painter, rect: Rect, symbol: u8) {
+        if Some(symbol) == self.board.settings.blank_symbol() {
+            // Blank pegs are drawn as an empty hole
+            painter.circle(
+                rect.center(),
+                rect.width() / 2.0,
+                egui::Color32::from_rgb(20, 20, 20),
+                egui::Stroke::new(2.0, egui::Color32::from_gray(90)),
+            );
+            return;
+        }
+        if self.board.settings.alphabet == Alphabet::Digits {
+            painter.circle_filled(
+                rect.center(),
+                rect.width() / 2.0,
+                egui::Color32::from_gray(60),
+            );
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                self.board.settings.format_code(&[symbol]),
+                egui::FontId::proportional(rect.height() * 0.6),
+                egui::Color32::WHITE,
+            );
+            return;
+        }
+        let color = self
+            .palette
+            .color(symbol)
+            .map_or(egui::Color32::GRAY, |color| color.to_egui_color());
+        painter.circle_filled(rect.center(), rect.width() / 2.0, color);
+    }
+}
+
 impl Widget for MastermindWidget<'_> {
     fn ui(self, ui: &mut Ui) -> Response {
         //let desired_size = ui.spacing().interact_size.y * 1.5; // example size
@@ -41,38 +94,63 @@ impl Widget for MastermindWidget<'_> {
         const GRID_SIZE: f32 = 40.0;
         const GRID_SPACING_Y: f32 = 10.0;
 
-        let height = (GRID_SIZE + GRID_SPACING_Y) * (self.board.settings.max_tries + 2) as f32;
+        let rows = self.board.settings.total_tries();
+        let height = (GRID_SIZE + GRID_SPACING_Y) * (rows + 2) as f32;
+        // Guesses of a static batch show up before they are answered
+        let played: Vec<&Guess> = self
+            .board
+            .state
+            .guesses
+            .iter()
+            .chain(&self.board.state.pending)
+            .collect();
         let (rect, response) = ui.allocate_exact_size(egui::vec2(500., height), Sense::click());
-        let mut grid_ui = ui.child_ui(rect, *ui.layout(), None);
+        let mut grid_ui = ui.new_child(egui::UiBuilder::new().max_rect(rect).layout(*ui.layout()));
 
         egui::Grid::new("mastermind_grid")
             .spacing([10.0, GRID_SPACING_Y])
             .show(&mut grid_ui, |ui| {
-                for row in 0..self.board.settings.max_tries {
+                for row in 0..rows {
                     for col in 0..self.board.settings.code_length {
-                        let (rect, response) = ui.allocate_exact_size(
+                        let (rect, _) = ui.allocate_exact_size(
                             egui::Vec2::splat(GRID_SIZE),
                             egui::Sense::hover(),
                         );
 
-                        let color = match (self.board.state.guesses.get(row as usize)) {
-                            None => egui::Color32::from_rgb(20, 20, 20),
-                            Some(guess) => self.board.settings.colors
-                                [guess.0[col as usize] as usize]
-                                .to_egui_color(),
-                        };
-
-                        ui.painter()
-                            .circle_filled(rect.center(), rect.width() / 2.0, color);
+                        match (played.get(row as usize)) {
+                            None => {
+                                ui.painter().circle_filled(
+                                    rect.center(),
+                                    rect.width() / 2.0,
+                                    egui::Color32::from_rgb(20, 20, 20),
+                                );
+                            }
+                            Some(guess) => {
+                                self.paint_peg(ui.painter(), rect, guess.0[col as usize]);
+                                let verdict = self
+                                    .board
+                                    .state
+                                    .verdicts
+                                    .get(row as usize)
+                                    .and_then(|verdicts| verdicts.get(col as usize));
+                                if let Some(verdict) = verdict {
+                                    ui.painter().circle_stroke(
+                                        rect.center(),
+                                        rect.width() / 2.0 - 1.5,
+                                        egui::Stroke::new(3.0, verdict_color(*verdict)),
+                                    );
+                                }
+                            }
+                        }
                     }
 
                     let answer_opt = self.board.state.answers.get(row as usize);
                     let mut markers = Vec::new();
-                    if let Some(answer) = answer_opt {
-                        if let GameState::GuessAnswer(right_pos, right_not_pos) = answer {
-                            markers.append(&mut vec![1; *right_pos as usize]);
-                            markers.append(&mut vec![2; *right_not_pos as usize]);
-                        }
+                    // Positional answers are shown on the pegs themselves
+                    let positional = self.board.settings.feedback == FeedbackMode::Positional;
+                    if let Some(answer) = answer_opt.filter(|_| !positional) {
+                        markers.append(&mut vec![1; answer.exact as usize]);
+                        markers.append(&mut vec![2; answer.misplaced as usize]);
                     }
 
                     let max_circle_count = self.board.settings.code_length as usize;
@@ -89,7 +167,7 @@ impl Widget for MastermindWidget<'_> {
                     ui.vertical(|ui| {
                         ui.horizontal(|ui| {
                             for i in 0..max_circle_count / 2 {
-                                let (rect, response) = ui.allocate_exact_size(
+                                let (rect, _) = ui.allocate_exact_size(
                                     egui::Vec2::splat(GRID_SIZE / 2.0),
                                     egui::Sense::hover(),
                                 );
@@ -108,7 +186,7 @@ impl Widget for MastermindWidget<'_> {
                         });
                         ui.horizontal(|ui| {
                             for i in max_circle_count / 2..max_circle_count {
-                                let (rect, response) = ui.allocate_exact_size(
+                                let (rect, _) = ui.allocate_exact_size(
                                     egui::Vec2::splat(GRID_SIZE / 2.0),
                                     egui::Sense::hover(),
                                 );
@@ -139,14 +217,9 @@ impl Widget for MastermindWidget<'_> {
                         if (response.clicked()) {
                             self.guess_state.0[col as usize] += 1;
                             self.guess_state.0[col as usize] %=
-                                self.board.settings.colors.len() as i16;
+                                self.board.settings.symbol_count() as i16;
                         }
-                        ui.painter().circle_filled(
-                            rect.center(),
-                            rect.width() / 2.0,
-                            self.board.settings.colors[self.guess_state.0[col as usize] as usize]
-                                .to_egui_color(),
-                        );
+                        self.paint_peg(ui.painter(), rect, self.guess_state.0[col as usize] as u8);
                     });
                 }
             });